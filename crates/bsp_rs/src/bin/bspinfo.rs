@@ -0,0 +1,40 @@
+//! Prints lump usage against the engine limits, textures and WADs of a map
+//!
+//! ```text
+//! bspinfo [-json] <map.bsp>
+//! ```
+
+use std::{env, fs, process::ExitCode};
+
+use bsp_rs::read_bsp_stats;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (json, path) = match args.as_slice() {
+        [path] => (false, path),
+        [flag, path] if flag == "-json" => (true, path),
+        _ => {
+            eprintln!("usage: bspinfo [-json] <map.bsp>");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("couldn't read {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match read_bsp_stats(&bytes) {
+        Ok(stats) if json => println!("{}", stats.to_json()),
+        Ok(stats) => print!("{}", stats),
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,54 @@
+//! Exports a map's entity lump to a `.ent` file or imports one back
+//!
+//! ```text
+//! ripent -export <map.bsp> [map.ent]
+//! ripent -import <map.bsp> [map.ent]
+//! ```
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use bsp_rs::{export_ent, import_ent, read_bsp};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (mode, bsp_path, ent_path) = match args.as_slice() {
+        [mode, bsp] => (
+            mode,
+            PathBuf::from(bsp),
+            PathBuf::from(bsp).with_extension("ent"),
+        ),
+        [mode, bsp, ent] => (mode, PathBuf::from(bsp), PathBuf::from(ent)),
+        _ => {
+            eprintln!("usage: ripent (-export | -import) <map.bsp> [map.ent]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let bytes = match fs::read(&bsp_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("couldn't read {}: {}", bsp_path.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match mode.as_str() {
+        "-export" => read_bsp(&bytes)
+            .map_err(|err| err.to_string())
+            .and_then(|bsp| export_ent(&bsp).map_err(|err| err.to_string()))
+            .and_then(|ent| fs::write(&ent_path, ent).map_err(|err| err.to_string())),
+        "-import" => fs::read_to_string(&ent_path)
+            .map_err(|err| format!("couldn't read {}: {}", ent_path.display(), err))
+            .and_then(|ent| import_ent(&bytes, &ent).map_err(|err| err.to_string()))
+            .and_then(|bytes| fs::write(&bsp_path, bytes).map_err(|err| err.to_string())),
+        _ => Err(format!("unknown mode \"{}\"", mode)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
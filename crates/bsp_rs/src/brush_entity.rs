@@ -0,0 +1,227 @@
+use crate::{BoundBox, Bsp, Entity, Model, Rgb, Vec3};
+
+/// How an entity is blended, the `rendermode` key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    #[default]
+    Normal,
+    /// Solid [BrushEntity::render_color] with [BrushEntity::render_amount] alpha
+    Color,
+    /// Textured with [BrushEntity::render_amount] alpha
+    Texture,
+    /// Sprites only, sized by distance
+    Glow,
+    /// Alpha tested, like textures prefixed with `{`
+    Solid,
+    Additive,
+}
+
+impl RenderMode {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        Some(match value {
+            0 => RenderMode::Normal,
+            1 => RenderMode::Color,
+            2 => RenderMode::Texture,
+            3 => RenderMode::Glow,
+            4 => RenderMode::Solid,
+            5 => RenderMode::Additive,
+            _ => return None,
+        })
+    }
+}
+
+/// Render effect, the `renderfx` key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderFx {
+    #[default]
+    None,
+    PulseSlow,
+    PulseFast,
+    PulseSlowWide,
+    PulseFastWide,
+    FadeSlow,
+    FadeFast,
+    SolidSlow,
+    SolidFast,
+    StrobeSlow,
+    StrobeFast,
+    StrobeFaster,
+    FlickerSlow,
+    FlickerFast,
+    NoDissipation,
+    Distort,
+    Hologram,
+    DeadPlayer,
+    Explode,
+    GlowShell,
+    ClampMinScale,
+    LightMultiplier,
+}
+
+impl RenderFx {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        Some(match value {
+            0 => RenderFx::None,
+            1 => RenderFx::PulseSlow,
+            2 => RenderFx::PulseFast,
+            3 => RenderFx::PulseSlowWide,
+            4 => RenderFx::PulseFastWide,
+            5 => RenderFx::FadeSlow,
+            6 => RenderFx::FadeFast,
+            7 => RenderFx::SolidSlow,
+            8 => RenderFx::SolidFast,
+            9 => RenderFx::StrobeSlow,
+            10 => RenderFx::StrobeFast,
+            11 => RenderFx::StrobeFaster,
+            12 => RenderFx::FlickerSlow,
+            13 => RenderFx::FlickerFast,
+            14 => RenderFx::NoDissipation,
+            15 => RenderFx::Distort,
+            16 => RenderFx::Hologram,
+            17 => RenderFx::DeadPlayer,
+            18 => RenderFx::Explode,
+            19 => RenderFx::GlowShell,
+            20 => RenderFx::ClampMinScale,
+            21 => RenderFx::LightMultiplier,
+            _ => return None,
+        })
+    }
+}
+
+/// An entity drawn with one of the map's models, the world included
+#[derive(Debug, Clone, Copy)]
+pub struct BrushEntity<'a> {
+    /// Index into entities
+    pub entity_index: usize,
+    pub entity: &'a Entity,
+    /// Index into models
+    pub model_index: usize,
+    pub model: &'a Model,
+    /// Models of entities with an `origin` are built around the world origin
+    pub origin: Vec3,
+    /// Pitch, yaw and roll in degrees
+    pub angles: Vec3,
+    pub render_mode: RenderMode,
+    /// Alpha of [RenderMode::Color], [RenderMode::Texture] and
+    /// [RenderMode::Additive]
+    pub render_amount: u8,
+    pub render_color: Rgb,
+    pub render_fx: RenderFx,
+}
+
+impl BrushEntity<'_> {
+    /// Model to world matrix, row major with the translation in the last column
+    ///
+    /// Brush models are rotated by yaw around Z, then by the negated pitch
+    /// around Y, then roll around X, like the engine's `R_RotateForEntity`.
+    /// A positive pitch tilts the model's +X up.
+    pub fn transform(&self) -> [[f32; 4]; 4] {
+        let (sp, cp) = (-self.angles.x).to_radians().sin_cos();
+        let (sy, cy) = self.angles.y.to_radians().sin_cos();
+        let (sr, cr) = self.angles.z.to_radians().sin_cos();
+
+        [
+            [
+                cy * cp,
+                cy * sp * sr - sy * cr,
+                cy * sp * cr + sy * sr,
+                self.origin.x,
+            ],
+            [
+                sy * cp,
+                sy * sp * sr + cy * cr,
+                sy * sp * cr - cy * sr,
+                self.origin.y,
+            ],
+            [-sp, cp * sr, cp * cr, self.origin.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+
+    /// Moves a point of the model into the world
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        let m = self.transform();
+        let row = |r: [f32; 4]| r[0] * point.x + r[1] * point.y + r[2] * point.z + r[3];
+        Vec3::new(row(m[0]), row(m[1]), row(m[2]))
+    }
+
+    /// World space bounds of the model
+    pub fn bounds(&self) -> BoundBox {
+        let BoundBox { min, max } = self.model.bounding_box;
+        let mut bounds = BoundBox {
+            min: Vec3::new(f32::MAX, f32::MAX, f32::MAX),
+            max: Vec3::new(f32::MIN, f32::MIN, f32::MIN),
+        };
+
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            );
+            let p = self.transform_point(corner);
+            bounds.min = Vec3::new(
+                bounds.min.x.min(p.x),
+                bounds.min.y.min(p.y),
+                bounds.min.z.min(p.z),
+            );
+            bounds.max = Vec3::new(
+                bounds.max.x.max(p.x),
+                bounds.max.y.max(p.y),
+                bounds.max.z.max(p.z),
+            );
+        }
+
+        bounds
+    }
+}
+
+impl Bsp {
+    /// Pairs the world and every entity with a `"model" "*N"` key with its model
+    ///
+    /// Only `angles` rotates a model, the `angle` key of doors and buttons
+    /// is the direction they move in.
+    pub fn brush_entities(&self) -> Vec<BrushEntity<'_>> {
+        let mut brush_entities = Vec::new();
+
+        for (entity_index, entity) in self.entities.iter().enumerate() {
+            let model_index = if entity_index == 0 {
+                Some(0)
+            } else {
+                entity
+                    .get("model")
+                    .and_then(|model| model.strip_prefix('*'))
+                    .and_then(|index| index.parse::<usize>().ok())
+            };
+            let Some((model_index, model)) =
+                model_index.and_then(|index| Some((index, self.models.get(index)?)))
+            else {
+                continue;
+            };
+
+            let zero = Vec3::new(0.0, 0.0, 0.0);
+            brush_entities.push(BrushEntity {
+                entity_index,
+                entity,
+                model_index,
+                model,
+                origin: entity.get_vec3("origin").unwrap_or(zero),
+                angles: entity.get_vec3("angles").unwrap_or(zero),
+                render_mode: entity
+                    .get_i32("rendermode")
+                    .and_then(RenderMode::from_i32)
+                    .unwrap_or_default(),
+                render_amount: entity.get_i32("renderamt").unwrap_or(0).clamp(0, 255) as u8,
+                render_color: entity
+                    .get_color("rendercolor")
+                    .unwrap_or(Rgb { r: 0, g: 0, b: 0 }),
+                render_fx: entity
+                    .get_i32("renderfx")
+                    .and_then(RenderFx::from_i32)
+                    .unwrap_or_default(),
+            });
+        }
+
+        brush_entities
+    }
+}
@@ -1,11 +1,80 @@
 use std::{
+    error::Error,
+    f32::consts::PI,
+    fmt,
     io::{self, ErrorKind},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-use image::{ImageError, ImageResult};
+use image::{imageops, ImageError, ImageResult};
+
+use crate::{Bsp, Vec3};
 
 const SIDES: [&str; 6] = ["rt", "lf", "up", "dn", "bk", "ft"];
+/// Image formats the engine accepts for sky sides, in the order it tries them
+const SIDE_EXTENSIONS: [&str; 2] = ["tga", "bmp"];
+
+/// Directory of sky sides inside a game directory
+pub const SKY_DIRECTORY: &str = "gfx/env";
+/// Sky the engine falls back to when `worldspawn` has no `skyname`
+pub const DEFAULT_SKY_NAME: &str = "desert";
+
+/// Error while reading the sides of a [Cubemap]
+#[derive(Debug)]
+pub enum CubemapError {
+    /// The side was found in none of the directories
+    MissingSide(String),
+    /// The side couldn't be decoded
+    Image { side: String, error: ImageError },
+    /// The side isn't square or differs in size from the first one, and
+    /// [CubemapOptions::resample] is off
+    InvalidDimensions {
+        side: String,
+        width: u32,
+        height: u32,
+        /// Size of the first side
+        expected: u32,
+    },
+}
+
+impl fmt::Display for CubemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CubemapError::MissingSide(side) => write!(f, "missing sky side {}", side),
+            CubemapError::Image { side, error } => {
+                write!(f, "couldn't read sky side {}: {}", side, error)
+            }
+            CubemapError::InvalidDimensions {
+                side,
+                width,
+                height,
+                expected,
+            } => write!(
+                f,
+                "sky side {} is {}x{}, expected {}x{}",
+                side, width, height, expected, expected
+            ),
+        }
+    }
+}
+
+impl Error for CubemapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CubemapError::Image { error, .. } => Some(error),
+            CubemapError::MissingSide(_) | CubemapError::InvalidDimensions { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CubemapOptions {
+    /// Resizes sides of differing or non-square sizes instead of failing,
+    /// to the largest side unless [CubemapOptions::dimension] is set
+    pub resample: bool,
+    /// Size every side is resized to when resampling
+    pub dimension: Option<u32>,
+}
 
 #[derive(Debug)]
 pub struct Cubemap {
@@ -13,39 +82,221 @@ pub struct Cubemap {
     pub sides: [Vec<u8>; 6],
 }
 
+/// Coordinate system a cubemap is converted to, see [Cubemap::faces]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CubemapLayout {
+    /// OpenGL, Vulkan and glTF, the engine's Y becoming -Z
+    #[default]
+    RightHandedYUp,
+    /// Direct3D, the engine's Y becoming Z
+    LeftHandedYUp,
+}
+
+impl Cubemap {
+    /// Color of the sky in a direction of the engine's Z up coordinates
+    pub fn sample(&self, direction: Vec3) -> [u8; 4] {
+        let Vec3 { x, y, z } = direction;
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+        // Side and its s and t coordinates, like the engine's `vec_to_st`
+        let (side, s, t) = if ax >= ay && ax >= az {
+            if x > 0.0 {
+                (0, -y / ax, z / ax)
+            } else {
+                (1, y / ax, z / ax)
+            }
+        } else if ay >= az {
+            if y > 0.0 {
+                (4, x / ay, z / ay)
+            } else {
+                (5, -x / ay, z / ay)
+            }
+        } else if z > 0.0 {
+            (2, -y / az, -x / az)
+        } else {
+            (3, -y / az, x / az)
+        };
+
+        let dimension = self.dimension as usize;
+        let pixel = |st: f32| {
+            (((st + 1.0) * 0.5 * dimension as f32) as usize).min(dimension.saturating_sub(1))
+        };
+        let offset = (pixel(-t) * dimension + pixel(s)) * 4;
+        self.sides[side]
+            .get(offset..offset + 4)
+            .map_or([0; 4], |rgba| [rgba[0], rgba[1], rgba[2], rgba[3]])
+    }
+
+    /// Reorients and reorders the sides into the +X, -X, +Y, -Y, +Z, -Z faces
+    /// graphics APIs expect, sampling each face with directions of `layout`
+    pub fn faces(&self, layout: CubemapLayout) -> [Vec<u8>; 6] {
+        let dimension = self.dimension as usize;
+        let face = |index: usize| {
+            let mut pixels = Vec::with_capacity(dimension * dimension * 4);
+            for row in 0..dimension {
+                for column in 0..dimension {
+                    let s = (column as f32 + 0.5) / dimension as f32 * 2.0 - 1.0;
+                    let t = (row as f32 + 0.5) / dimension as f32 * 2.0 - 1.0;
+                    let Vec3 { x, y, z } = match index {
+                        0 => Vec3::new(1.0, -t, -s),
+                        1 => Vec3::new(-1.0, -t, s),
+                        2 => Vec3::new(s, 1.0, t),
+                        3 => Vec3::new(s, -1.0, -t),
+                        4 => Vec3::new(s, -t, 1.0),
+                        _ => Vec3::new(-s, -t, -1.0),
+                    };
+                    let direction = match layout {
+                        CubemapLayout::RightHandedYUp => Vec3::new(x, -z, y),
+                        CubemapLayout::LeftHandedYUp => Vec3::new(x, z, y),
+                    };
+                    pixels.extend_from_slice(&self.sample(direction));
+                }
+            }
+            pixels
+        };
+
+        [face(0), face(1), face(2), face(3), face(4), face(5)]
+    }
+
+    /// Projects the sky into a `width` by `width / 2` panorama, the
+    /// engine's +X being in the middle and +Z at the top
+    pub fn to_equirectangular(&self, width: u32) -> Vec<u8> {
+        let height = width / 2;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+
+        for row in 0..height {
+            let pitch = PI * (0.5 - (row as f32 + 0.5) / height as f32);
+            for column in 0..width {
+                // Yaw grows to the left, like it does in the engine
+                let yaw = PI * (1.0 - (column as f32 + 0.5) / width as f32 * 2.0);
+                let direction = Vec3::new(
+                    pitch.cos() * yaw.cos(),
+                    pitch.cos() * yaw.sin(),
+                    pitch.sin(),
+                );
+                pixels.extend_from_slice(&self.sample(direction));
+            }
+        }
+
+        pixels
+    }
+}
+
 #[inline]
 pub fn read_cubemap<P: AsRef<Path>>(name: &str, path: P) -> ImageResult<Cubemap> {
-    let mut dimension = 0;
-    let mut sides = [
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-    ];
-
-    for (i, postfix) in SIDES.iter().enumerate() {
-        let file_name = format!("{}{}", name, postfix);
-        let file_path = path.as_ref().join(file_name).with_extension("tga");
-        let image = image::open(file_path)?.to_rgba8();
-
-        if dimension == 0 {
-            dimension = image.width();
-        } else if dimension != image.width() || image.width() != image.height() {
-            return Err(ImageError::IoError(io::Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Invalid texture dimensions. Side {}: Width {}, Height {}.",
-                    i,
-                    image.width(),
-                    image.height()
-                ),
-            )));
+    read_cubemap_with(name, path, &CubemapOptions::default()).map_err(|err| match err {
+        CubemapError::Image { error, .. } => error,
+        CubemapError::MissingSide(_) => {
+            ImageError::IoError(io::Error::new(ErrorKind::NotFound, err.to_string()))
+        }
+        CubemapError::InvalidDimensions { .. } => {
+            ImageError::IoError(io::Error::new(ErrorKind::InvalidData, err.to_string()))
         }
+    })
+}
 
-        sides[i] = image.into_raw();
+/// Reads the sides of a sky from a directory, see [CubemapOptions]
+pub fn read_cubemap_with<P: AsRef<Path>>(
+    name: &str,
+    path: P,
+    options: &CubemapOptions,
+) -> Result<Cubemap, CubemapError> {
+    read_cubemap_from(name, &[path.as_ref().to_path_buf()], options)
+}
+
+impl Bsp {
+    /// Sky name from the `worldspawn` entity
+    pub fn sky_name(&self) -> &str {
+        self.entities
+            .first()
+            .and_then(|world| world.get("skyname"))
+            .unwrap_or(DEFAULT_SKY_NAME)
+    }
+
+    /// Reads the map's sky from the [SKY_DIRECTORY] of game directories,
+    /// the first one having priority
+    ///
+    /// Like the engine, every side is looked up on its own and may come from
+    /// a different directory. With `hd`, the `_hd` variant of each directory
+    /// (`valve_hd` for `valve`) is searched before it.
+    pub fn skybox<P: AsRef<Path>>(
+        &self,
+        game_directories: &[P],
+        hd: bool,
+        options: &CubemapOptions,
+    ) -> Result<Cubemap, CubemapError> {
+        let mut directories = Vec::new();
+        for directory in game_directories {
+            let directory = directory.as_ref();
+            if let Some(name) = directory.file_name().filter(|_| hd) {
+                let mut hd_name = name.to_os_string();
+                hd_name.push("_hd");
+                directories.push(directory.with_file_name(hd_name).join(SKY_DIRECTORY));
+            }
+            directories.push(directory.join(SKY_DIRECTORY));
+        }
+
+        read_cubemap_from(self.sky_name(), &directories, options)
     }
+}
+
+fn read_cubemap_from(
+    name: &str,
+    directories: &[PathBuf],
+    options: &CubemapOptions,
+) -> Result<Cubemap, CubemapError> {
+    let mut images = Vec::with_capacity(SIDES.len());
+    for postfix in SIDES {
+        let side = format!("{}{}", name, postfix);
+        let file_path = directories
+            .iter()
+            .flat_map(|directory| {
+                SIDE_EXTENSIONS
+                    .iter()
+                    .map(|extension| directory.join(format!("{}.{}", side, extension)))
+            })
+            .find(|file_path| file_path.is_file())
+            .ok_or_else(|| CubemapError::MissingSide(side.clone()))?;
+        let image = match image::open(file_path) {
+            Ok(image) => image.to_rgba8(),
+            Err(error) => return Err(CubemapError::Image { side, error }),
+        };
+        images.push((side, image));
+    }
+
+    let dimension = if options.resample {
+        options.dimension.unwrap_or_else(|| {
+            images
+                .iter()
+                .map(|(_, image)| image.width().max(image.height()))
+                .max()
+                .unwrap_or(0)
+        })
+    } else {
+        images[0].1.width()
+    };
+
+    let sides = images
+        .into_iter()
+        .map(|(side, image)| {
+            if image.width() == dimension && image.height() == dimension {
+                Ok(image.into_raw())
+            } else if options.resample {
+                let image = imageops::resize(&image, dimension, dimension, imageops::Triangle);
+                Ok(image.into_raw())
+            } else {
+                Err(CubemapError::InvalidDimensions {
+                    side,
+                    width: image.width(),
+                    height: image.height(),
+                    expected: dimension,
+                })
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(Cubemap { dimension, sides })
+    Ok(Cubemap {
+        dimension,
+        sides: sides.try_into().expect("every side was read"),
+    })
 }
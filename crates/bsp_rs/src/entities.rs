@@ -1,60 +1,411 @@
-use std::collections::HashMap;
+use std::{error::Error, fmt};
 
-use com_goldsrc_formats::Vec3;
+use com_goldsrc_formats::{Rgb, Vec3};
 
-pub type Entity = HashMap<String, EntityValue>;
+use crate::TextCursor;
 
-#[derive(Debug, PartialEq)]
-pub enum EntityValue {
-    String(String),
-    Number(f32),
-    Vec3(Vec3),
+/// A single entity, its key/value pairs kept in file order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entity {
+    pub properties: Vec<(String, String)>,
+}
+
+impl Entity {
+    pub fn new(classname: &str) -> Self {
+        let mut entity = Self::default();
+        entity.set("classname", classname);
+        entity
+    }
+
+    /// Returns the value of a key, the last one winning for duplicates like in the engine
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns every value of a key, in file order
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.properties
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn classname(&self) -> Option<&str> {
+        self.get("classname")
+    }
+
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get(key)?.trim().parse().ok()
+    }
+
+    /// Parses an integer value, accepting decimal values like `atoi` does
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        let value = self.get(key)?.trim();
+        value
+            .parse()
+            .ok()
+            .or_else(|| value.parse::<f32>().ok().map(|v| v as i32))
+    }
+
+    /// Parses a `"x y z"` value
+    pub fn get_vec3(&self, key: &str) -> Option<Vec3> {
+        let [x, y, z] = parse_components(self.get(key)?)?;
+        Some(Vec3::new(x, y, z))
+    }
+
+    /// Parses a `"r g b"` value, ignoring a trailing brightness like `_light` has
+    pub fn get_color(&self, key: &str) -> Option<Rgb> {
+        let mut components = self.get(key)?.split_whitespace();
+        let mut next = || -> Option<u8> { Some(components.next()?.parse::<f32>().ok()? as u8) };
+        Some(Rgb {
+            r: next()?,
+            g: next()?,
+            b: next()?,
+        })
+    }
+
+    pub fn targetname(&self) -> Option<&str> {
+        self.get("targetname")
+    }
+
+    /// Sets the value of a key, replacing its first occurrence in place and
+    /// dropping duplicates, or appending it when missing
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.properties.iter().position(|(k, _)| k == key) {
+            Some(i) => {
+                self.properties[i].1 = value;
+                let mut index = 0;
+                self.properties.retain(|(k, _)| {
+                    index += 1;
+                    index - 1 <= i || k != key
+                });
+            }
+            None => self.properties.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn set_f32(&mut self, key: &str, value: f32) {
+        self.set(key, format_f32(value));
+    }
+
+    pub fn set_i32(&mut self, key: &str, value: i32) {
+        self.set(key, value.to_string());
+    }
+
+    pub fn set_vec3(&mut self, key: &str, value: Vec3) {
+        self.set(
+            key,
+            format!(
+                "{} {} {}",
+                format_f32(value.x),
+                format_f32(value.y),
+                format_f32(value.z)
+            ),
+        );
+    }
+
+    pub fn set_color(&mut self, key: &str, value: Rgb) {
+        self.set(key, format!("{} {} {}", value.r, value.g, value.b));
+    }
+
+    /// Removes every occurrence of a key, returning the value [Entity::get] would have
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let mut removed = None;
+        self.properties.retain_mut(|(k, v)| {
+            if k == key {
+                removed = Some(std::mem::take(v));
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+}
+
+/// Formats a number the shortest way that parses back to the same value,
+/// without exponent since the engine and compilers read values with `atof`
+pub fn format_f32(value: f32) -> String {
+    if value == 0.0 {
+        // Avoid writing "-0"
+        return "0".to_string();
+    }
+    value.to_string()
+}
+
+pub fn entities_by_classname<'a>(
+    entities: &'a [Entity],
+    classname: &'a str,
+) -> impl Iterator<Item = &'a Entity> + 'a {
+    entities
+        .iter()
+        .filter(move |e| e.classname() == Some(classname))
+}
+
+pub fn entities_by_classname_mut<'a>(
+    entities: &'a mut [Entity],
+    classname: &'a str,
+) -> impl Iterator<Item = &'a mut Entity> + 'a {
+    entities
+        .iter_mut()
+        .filter(move |e| e.classname() == Some(classname))
+}
+
+pub fn entities_by_targetname<'a>(
+    entities: &'a [Entity],
+    targetname: &'a str,
+) -> impl Iterator<Item = &'a Entity> + 'a {
+    entities
+        .iter()
+        .filter(move |e| e.targetname() == Some(targetname))
 }
 
-pub fn parse_entities(data: &str) -> Vec<Entity> {
-    let mut map = Vec::new();
+pub fn entities_by_targetname_mut<'a>(
+    entities: &'a mut [Entity],
+    targetname: &'a str,
+) -> impl Iterator<Item = &'a mut Entity> + 'a {
+    entities
+        .iter_mut()
+        .filter(move |e| e.targetname() == Some(targetname))
+}
+
+fn parse_components<const N: usize>(value: &str) -> Option<[f32; N]> {
+    let mut components = [0.0; N];
+    let mut split = value.split_whitespace();
+    for component in &mut components {
+        *component = split.next()?.parse().ok()?;
+    }
+    split.next().is_none().then_some(components)
+}
+
+/// Syntax error in an entity lump
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityParseError {
+    /// Line of the offending token, starting at 1
+    pub line: usize,
+    /// Column of the offending token in characters, starting at 1
+    pub column: usize,
+    pub kind: EntityParseErrorKind,
+}
 
-    for line in data.lines() {
-        match line {
-            "{" => map.push(Entity::default()),
-            "}" => {}
-            line => process_line(line, &mut map),
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityParseErrorKind {
+    /// Something other than `{` outside of an entity
+    ExpectedOpenBrace(String),
+    /// A `{` inside of an entity
+    UnexpectedOpenBrace,
+    /// A key without a value before the closing `}`
+    MissingValue(String),
+    /// A quoted string without its closing quote
+    UnterminatedString,
+    /// The data ended inside of an entity
+    UnexpectedEof,
+}
+
+impl fmt::Display for EntityParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            EntityParseErrorKind::ExpectedOpenBrace(token) => {
+                write!(f, "expected '{{', found \"{}\"", token)
+            }
+            EntityParseErrorKind::UnexpectedOpenBrace => write!(f, "unexpected '{{' in entity"),
+            EntityParseErrorKind::MissingValue(key) => write!(f, "key \"{}\" has no value", key),
+            EntityParseErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            EntityParseErrorKind::UnexpectedEof => write!(f, "unexpected end of data in entity"),
         }
     }
+}
+
+impl Error for EntityParseError {}
 
-    map
+/// A key or value [write_entities] can't write, the format having no way
+/// to escape double quotes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityWriteError {
+    /// Index of the entity
+    pub entity: usize,
+    pub key: String,
 }
 
-fn process_line(line: &str, map: &mut Vec<Entity>) {
-    let mut kv = line.split('"');
-    if let Some(key) = kv.nth(1) {
-        if let Some(value) = kv.nth(1) {
-            if let Some(entity) = map.last_mut() {
-                if let Ok(number) = value.parse::<f32>() {
-                    entity.insert(key.to_string(), EntityValue::Number(number));
-                    return;
-                } else if let Some(vec3) = parse_vec3(value) {
-                    entity.insert(key.to_string(), EntityValue::Vec3(vec3));
-                    return;
+impl fmt::Display for EntityWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key \"{}\" of entity {} contains a double quote",
+            self.key, self.entity
+        )
+    }
+}
+
+impl Error for EntityWriteError {}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    OpenBrace,
+    CloseBrace,
+    /// A quoted or bare string
+    String(&'a str),
+}
+
+/// Splits entity data into tokens the way the engine's `COM_Parse` does
+struct Tokenizer<'a> {
+    cursor: TextCursor<'a>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            cursor: TextCursor::new(data),
+        }
+    }
+
+    fn error(&self, line: usize, column: usize, kind: EntityParseErrorKind) -> EntityParseError {
+        EntityParseError { line, column, kind }
+    }
+
+    fn eof(&self) -> EntityParseError {
+        self.error(
+            self.cursor.line,
+            self.cursor.column,
+            EntityParseErrorKind::UnexpectedEof,
+        )
+    }
+
+    /// Returns the next token along with its line and column
+    fn next_token(&mut self) -> Result<Option<(Token<'a>, usize, usize)>, EntityParseError> {
+        self.cursor.skip_blank(|c| c == '\0');
+
+        let (line, column) = (self.cursor.line, self.cursor.column);
+        let Some((start, c)) = self.cursor.bump() else {
+            return Ok(None);
+        };
+
+        let token = match c {
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            '"' => match self.cursor.quoted(start) {
+                Some(string) => Token::String(string),
+                None => {
+                    return Err(self.error(line, column, EntityParseErrorKind::UnterminatedString))
                 }
-                entity.insert(key.to_string(), EntityValue::String(value.to_string()));
+            },
+            _ => Token::String(
+                self.cursor
+                    .word(start, |c| matches!(c, '{' | '}' | '"' | '\0')),
+            ),
+        };
+
+        Ok(Some((token, line, column)))
+    }
+}
+
+/// Parses entity lump text, keeping keys in order and duplicates intact
+pub fn parse_entities(data: &str) -> Result<Vec<Entity>, EntityParseError> {
+    let mut tokenizer = Tokenizer::new(data);
+    let mut entities = Vec::new();
+
+    while let Some((token, line, column)) = tokenizer.next_token()? {
+        match token {
+            Token::OpenBrace => {}
+            Token::CloseBrace => {
+                return Err(tokenizer.error(
+                    line,
+                    column,
+                    EntityParseErrorKind::ExpectedOpenBrace("}".to_string()),
+                ))
+            }
+            Token::String(s) => {
+                return Err(tokenizer.error(
+                    line,
+                    column,
+                    EntityParseErrorKind::ExpectedOpenBrace(s.to_string()),
+                ))
             }
         }
+
+        let mut entity = Entity::default();
+        loop {
+            let key = match tokenizer.next_token()? {
+                Some((Token::CloseBrace, _, _)) => break,
+                Some((Token::OpenBrace, line, column)) => {
+                    return Err(tokenizer.error(
+                        line,
+                        column,
+                        EntityParseErrorKind::UnexpectedOpenBrace,
+                    ))
+                }
+                Some((Token::String(key), _, _)) => key,
+                None => return Err(tokenizer.eof()),
+            };
+
+            let value = match tokenizer.next_token()? {
+                Some((Token::String(value), _, _)) => value,
+                Some((_, line, column)) => {
+                    return Err(tokenizer.error(
+                        line,
+                        column,
+                        EntityParseErrorKind::MissingValue(key.to_string()),
+                    ))
+                }
+                None => return Err(tokenizer.eof()),
+            };
+
+            entity.properties.push((key.to_string(), value.to_string()));
+        }
+        entities.push(entity);
     }
+
+    Ok(entities)
 }
 
-fn parse_vec3(value: &str) -> Option<Vec3> {
-    let components: Vec<&str> = value.split_whitespace().collect();
+/// Writes entities back to entity lump text
+///
+/// Fails when a key or value contains a double quote, which the engine would
+/// read as the end of the string.
+pub fn write_entities(entities: &[Entity]) -> Result<String, EntityWriteError> {
+    let mut data = String::new();
+
+    for (i, entity) in entities.iter().enumerate() {
+        data.push_str("{\n");
+        write_entity_keys(&mut data, i, entity)?;
+        data.push_str("}\n");
+    }
+
+    Ok(data)
+}
 
-    if components.len() == 3 {
-        if let (Ok(x), Ok(y), Ok(z)) = (
-            components[0].parse(),
-            components[1].parse(),
-            components[2].parse(),
-        ) {
-            return Some(Vec3::new(x, y, z));
+/// Writes the `"key" "value"` lines of the entity at `index`, failing like
+/// [write_entities] does
+pub fn write_entity_keys(
+    data: &mut String,
+    index: usize,
+    entity: &Entity,
+) -> Result<(), EntityWriteError> {
+    for (key, value) in entity.iter() {
+        if key.contains('"') || value.contains('"') {
+            return Err(EntityWriteError {
+                entity: index,
+                key: key.to_string(),
+            });
         }
+        data.push('"');
+        data.push_str(key);
+        data.push_str("\" \"");
+        data.push_str(value);
+        data.push_str("\"\n");
     }
 
-    None
+    Ok(())
 }
@@ -0,0 +1,32 @@
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder};
+
+use crate::{MipTexture, Vec3};
+
+/// Encodes RGBA pixels as a PNG file
+pub(crate) fn encode_png(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(rgba, width, height, ColorType::Rgba8)
+        .expect("image size matches its pixels");
+    png
+}
+
+/// RGBA pixels of a texture stored in the map, `None` for WAD textures
+pub(crate) fn embedded_pixels(texture: &MipTexture) -> Option<Vec<u8>> {
+    let embedded = !texture.indices.is_empty() && !texture.palette.is_empty();
+    if embedded {
+        texture.pixels(0)
+    } else {
+        None
+    }
+}
+
+/// Whether a texture has transparent pixels, which its `{` prefix marks
+pub(crate) fn is_transparent(texture: &MipTexture) -> bool {
+    texture.name.starts_with('{')
+}
+
+/// Turns -0 into 0, which exporters would otherwise write as `-0`
+pub(crate) fn positive_zero(v: Vec3) -> Vec3 {
+    Vec3::new(v.x + 0.0, v.y + 0.0, v.z + 0.0)
+}
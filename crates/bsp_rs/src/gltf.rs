@@ -0,0 +1,377 @@
+use std::{f32::consts::PI, fmt::Write};
+
+use crate::{
+    export::{embedded_pixels, encode_png, is_transparent, positive_zero},
+    stats::json_string,
+    Bsp, Entity, Vec3,
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Size of a unit in meters, the engine's units being about an inch
+pub const UNIT_SIZE: f32 = 0.0254;
+
+#[derive(Debug, Clone)]
+pub struct GltfOptions {
+    /// Multiplies every coordinate, glTF being in meters
+    pub scale: f32,
+}
+
+impl Default for GltfOptions {
+    fn default() -> Self {
+        Self { scale: UNIT_SIZE }
+    }
+}
+
+/// Collects the JSON objects and the binary chunk of a GLB file
+#[derive(Default)]
+struct Glb {
+    buffer: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+    images: Vec<String>,
+    textures: Vec<String>,
+    materials: Vec<String>,
+    meshes: Vec<String>,
+    nodes: Vec<String>,
+}
+
+impl Glb {
+    fn buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend_from_slice(bytes);
+        self.buffer.resize((self.buffer.len() + 3) & !3, 0);
+
+        let mut view = format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}",
+            offset,
+            bytes.len()
+        );
+        if let Some(target) = target {
+            write!(view, ",\"target\":{}", target).unwrap();
+        }
+        view.push('}');
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn floats<const N: usize>(&mut self, values: &[[f32; N]], bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let view = self.buffer_view(&bytes, Some(ARRAY_BUFFER));
+
+        let mut accessor = format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"{}\"",
+            view,
+            FLOAT,
+            values.len(),
+            ["SCALAR", "VEC2", "VEC3", "VEC4"][N - 1]
+        );
+        // Positions need their bounds
+        if bounds {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for value in values {
+                for i in 0..N {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            write!(
+                accessor,
+                ",\"min\":{},\"max\":{}",
+                json_floats(&min),
+                json_floats(&max)
+            )
+            .unwrap();
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.buffer_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}",
+            view,
+            UNSIGNED_INT,
+            indices.len()
+        ));
+        self.accessors.len() - 1
+    }
+
+    /// Adds an RGBA image as a PNG texture
+    fn texture(&mut self, name: &str, width: u32, height: u32, rgba: &[u8]) -> usize {
+        let png = encode_png(rgba, width, height);
+        let view = self.buffer_view(&png, None);
+        self.images.push(format!(
+            "{{\"name\":{},\"bufferView\":{},\"mimeType\":\"image/png\"}}",
+            json_string(name),
+            view
+        ));
+        self.textures.push(format!(
+            "{{\"sampler\":0,\"source\":{}}}",
+            self.images.len() - 1
+        ));
+        self.textures.len() - 1
+    }
+
+    fn write(self) -> Vec<u8> {
+        let scene: Vec<_> = (0..self.nodes.len()).map(|i| i.to_string()).collect();
+        let mut json = format!(
+            "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"bsp_rs\"}},\
+             \"scene\":0,\"scenes\":[{{\"nodes\":[{}]}}],\
+             \"buffers\":[{{\"byteLength\":{}}}],\
+             \"samplers\":[{{\"wrapS\":10497,\"wrapT\":10497}}]",
+            scene.join(","),
+            self.buffer.len()
+        );
+        for (name, values) in [
+            ("bufferViews", &self.buffer_views),
+            ("accessors", &self.accessors),
+            ("images", &self.images),
+            ("textures", &self.textures),
+            ("materials", &self.materials),
+            ("meshes", &self.meshes),
+            ("nodes", &self.nodes),
+        ] {
+            if !values.is_empty() {
+                write!(json, ",\"{}\":[{}]", name, values.join(",")).unwrap();
+            }
+        }
+        json.push('}');
+
+        // Chunks are padded to 4 bytes, JSON with spaces
+        let mut json = json.into_bytes();
+        json.resize((json.len() + 3) & !3, b' ');
+
+        let length = 12 + 8 + json.len() + 8 + self.buffer.len();
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(self.buffer.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&self.buffer);
+
+        glb
+    }
+}
+
+impl Bsp {
+    /// Exports the map as a binary glTF file
+    ///
+    /// Every model becomes a mesh, its vertices having the texture
+    /// coordinates in `TEXCOORD_0` and the [lightmap atlas](Bsp::lightmap_atlas)
+    /// ones in `TEXCOORD_1`. glTF has no lightmap slot, so every material
+    /// references the atlas in its extras as `"lightmap":{"index":N,"texCoord":1}`.
+    /// Textures stored in WADs have no pixels in the map, fill
+    /// [Bsp::textures] from the WADs beforehand to embed them.
+    ///
+    /// Point entities become empty nodes with their key values as extras.
+    /// Coordinates are converted to glTF's Y up and scaled by
+    /// [GltfOptions::scale].
+    pub fn write_glb(&self, options: &GltfOptions) -> Vec<u8> {
+        let mut glb = Glb::default();
+
+        let atlas = self.lightmap_atlas();
+        let lightmap = glb.texture("lightmap", atlas.width, atlas.height, &atlas.pixels);
+
+        for texture in &self.textures {
+            let mut material = format!("{{\"name\":{}", json_string(&texture.name));
+            if let Some(pixels) = embedded_pixels(texture) {
+                let index = glb.texture(&texture.name, texture.width, texture.height, &pixels);
+                write!(
+                    material,
+                    ",\"pbrMetallicRoughness\":{{\"baseColorTexture\":{{\"index\":{}}},\
+                     \"metallicFactor\":0}}",
+                    index
+                )
+                .unwrap();
+                if is_transparent(texture) {
+                    material.push_str(",\"alphaMode\":\"MASK\"");
+                }
+            } else {
+                material.push_str(",\"pbrMetallicRoughness\":{\"metallicFactor\":0}");
+            }
+            write!(
+                material,
+                ",\"extras\":{{\"lightmap\":{{\"index\":{},\"texCoord\":1}}}}}}",
+                lightmap
+            )
+            .unwrap();
+            glb.materials.push(material);
+        }
+
+        let brush_entities = self.brush_entities();
+        for (i, mesh) in self.meshes().iter().enumerate() {
+            let mut primitives = Vec::new();
+            for group in &mesh.groups {
+                let positions: Vec<_> = group
+                    .vertices
+                    .iter()
+                    .map(|v| y_up(v.position * options.scale))
+                    .collect();
+                let normals: Vec<_> = group.vertices.iter().map(|v| y_up(v.normal)).collect();
+                let uvs: Vec<_> = group.vertices.iter().map(|v| v.uv).collect();
+                let lightmap_uvs: Vec<_> = group
+                    .vertices
+                    .iter()
+                    .map(|v| {
+                        atlas
+                            .uv(v.face as usize, v.lightmap_uv)
+                            .unwrap_or([0.0, 0.0])
+                    })
+                    .collect();
+
+                let position = glb.floats(&positions, true);
+                let normal = glb.floats(&normals, false);
+                let uv = glb.floats(&uvs, false);
+                let lightmap_uv = glb.floats(&lightmap_uvs, false);
+                let indices = glb.indices(&group.counter_clockwise_indices());
+
+                let mut primitive = format!(
+                    "{{\"attributes\":{{\"POSITION\":{},\"NORMAL\":{},\
+                     \"TEXCOORD_0\":{},\"TEXCOORD_1\":{}}},\"indices\":{}",
+                    position, normal, uv, lightmap_uv, indices
+                );
+                if (group.texture as usize) < self.textures.len() {
+                    write!(primitive, ",\"material\":{}", group.texture).unwrap();
+                }
+                primitive.push('}');
+                primitives.push(primitive);
+            }
+
+            if primitives.is_empty() {
+                continue;
+            }
+            glb.meshes.push(format!(
+                "{{\"name\":\"*{}\",\"primitives\":[{}]}}",
+                i,
+                primitives.join(",")
+            ));
+            let mesh = glb.meshes.len() - 1;
+
+            // Brush entities with an origin have their model around it
+            let model = format!("*{}", i);
+            let brush_entity = brush_entities
+                .iter()
+                .find(|brush_entity| brush_entity.model_index == i);
+            let entity = brush_entity.map(|brush_entity| brush_entity.entity);
+            let (origin, angles) = match brush_entity {
+                Some(brush_entity) if brush_entity.entity_index != 0 => {
+                    let zero = Vec3::new(0.0, 0.0, 0.0);
+                    // Brush models are pitched the other way, see BrushEntity::transform
+                    let angles = brush_entity.angles;
+                    let angles = Some(Vec3::new(-angles.x, angles.y, angles.z))
+                        .filter(|&angles| angles != zero);
+                    (Some(brush_entity.origin * options.scale), angles)
+                }
+                _ => (None, None),
+            };
+            let name = entity
+                .and_then(|entity| entity.classname())
+                .unwrap_or(&model);
+            glb.nodes
+                .push(entity_node(entity, name, origin, angles, Some(mesh)));
+        }
+
+        for entity in &self.entities[self.entities.len().min(1)..] {
+            if entity
+                .get("model")
+                .is_some_and(|model| model.starts_with('*'))
+            {
+                continue;
+            }
+            let Some(origin) = entity.get_vec3("origin") else {
+                continue;
+            };
+            let angles = entity
+                .get_vec3("angles")
+                .or_else(|| entity.get_f32("angle").map(|yaw| Vec3::new(0.0, yaw, 0.0)));
+            let name = entity.classname().unwrap_or("entity");
+            let origin = origin * options.scale;
+            glb.nodes
+                .push(entity_node(Some(entity), name, Some(origin), angles, None));
+        }
+
+        glb.write()
+    }
+}
+
+/// Writes a node named after an entity's `targetname`, or `fallback`
+fn entity_node(
+    entity: Option<&Entity>,
+    fallback: &str,
+    origin: Option<Vec3>,
+    angles: Option<Vec3>,
+    mesh: Option<usize>,
+) -> String {
+    let name = entity
+        .and_then(|entity| entity.targetname())
+        .unwrap_or(fallback);
+    let mut node = format!("{{\"name\":{}", json_string(name));
+
+    if let Some(mesh) = mesh {
+        write!(node, ",\"mesh\":{}", mesh).unwrap();
+    }
+    if let Some(origin) = origin {
+        let [x, y, z]: [f32; 3] = y_up(origin);
+        write!(node, ",\"translation\":{}", json_floats(&[x, y, z])).unwrap();
+    }
+    if let Some(angles) = angles {
+        write!(node, ",\"rotation\":{}", json_floats(&rotation(angles))).unwrap();
+    }
+    if let Some(entity) = entity {
+        node.push_str(",\"extras\":{");
+        for (i, (key, value)) in entity.iter().enumerate() {
+            if i > 0 {
+                node.push(',');
+            }
+            write!(node, "{}:{}", json_string(key), json_string(value)).unwrap();
+        }
+        node.push('}');
+    }
+
+    node.push('}');
+    node
+}
+
+/// Converts from the engine's Z up to glTF's Y up
+fn y_up(v: Vec3) -> [f32; 3] {
+    let v = positive_zero(Vec3::new(v.x, v.z, -v.y));
+    [v.x, v.y, v.z]
+}
+
+/// Converts pitch, yaw and roll in degrees to a glTF quaternion
+fn rotation(angles: Vec3) -> [f32; 4] {
+    let half = |degrees: f32| (degrees * PI / 360.0).sin_cos();
+    let (sp, cp) = half(angles.x);
+    let (sy, cy) = half(angles.y);
+    let (sr, cr) = half(angles.z);
+
+    // Yaw around Z, then pitch around Y, then roll around X
+    let x = cy * cp * sr - sy * sp * cr;
+    let y = cy * sp * cr + sy * cp * sr;
+    let z = sy * cp * cr - cy * sp * sr;
+    let w = cy * cp * cr + sy * sp * sr;
+
+    let [x, y, z] = y_up(Vec3::new(x, y, z));
+    [x, y, z, w]
+}
+
+fn json_floats(values: &[f32]) -> String {
+    let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
@@ -1,68 +1,236 @@
 use std::{
-    io::{Cursor, SeekFrom},
-    mem::size_of,
+    io::{Cursor, Read, Seek, SeekFrom},
     ops::Range,
 };
 
-use binrw::prelude::*;
+use binrw::{prelude::*, Endian};
 pub use com_goldsrc_formats::prelude::*;
 
+pub use brush_entity::*;
 pub use cubemap::*;
 pub use entities::*;
-
+#[cfg(feature = "gltf")]
+pub use gltf::*;
+pub use lightmap::*;
+pub use lightstyle::*;
+pub use lumps::*;
+pub use mesh::*;
+#[cfg(feature = "obj")]
+pub use obj::*;
+pub use ripent::*;
+pub use spawns::*;
+pub use stats::*;
+pub use text::*;
+pub use tree::*;
+pub use validate::*;
+
+mod brush_entity;
 mod cubemap;
 mod entities;
+#[cfg(any(feature = "gltf", feature = "obj"))]
+mod export;
+#[cfg(feature = "gltf")]
+mod gltf;
+mod lightmap;
+mod lightstyle;
+mod lumps;
+mod mesh;
+#[cfg(feature = "obj")]
+mod obj;
+mod ripent;
+mod spawns;
+mod stats;
+mod text;
+mod tree;
+mod validate;
+
+pub const LUMP_ENTITIES: usize = 0;
+pub const LUMP_PLANES: usize = 1;
+pub const LUMP_TEXTURES: usize = 2;
+pub const LUMP_VERTICES: usize = 3;
+pub const LUMP_VISIBILITY: usize = 4;
+pub const LUMP_NODES: usize = 5;
+pub const LUMP_TEXINFO: usize = 6;
+pub const LUMP_FACES: usize = 7;
+pub const LUMP_LIGHTING: usize = 8;
+pub const LUMP_CLIPNODES: usize = 9;
+pub const LUMP_LEAVES: usize = 10;
+pub const LUMP_MARKSURFACES: usize = 11;
+pub const LUMP_EDGES: usize = 12;
+pub const LUMP_SURFEDGES: usize = 13;
+pub const LUMP_MODELS: usize = 14;
+/// Number of lumps in the header
+pub const HEADER_LUMPS: usize = 15;
+
+/// Names of the lumps, indexed by the `LUMP_*` constants
+pub const LUMP_NAMES: [&str; HEADER_LUMPS] = [
+    "entities",
+    "planes",
+    "textures",
+    "vertices",
+    "visibility",
+    "nodes",
+    "texinfo",
+    "faces",
+    "lighting",
+    "clipnodes",
+    "leaves",
+    "marksurfaces",
+    "edges",
+    "surfedges",
+    "models",
+];
+
+/// Position of a lump in the file
+#[binread]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LumpEntry {
+    pub offset: u32,
+    pub length: u32,
+}
+
+/// Quake's BSP2 magic, read as a version
+pub const BSP2_VERSION: u32 = u32::from_le_bytes(*b"BSP2");
+/// The magic of RMQ's earlier BSP2 revision, read as a version
+pub const BSP2_RMQ_VERSION: u32 = u32::from_le_bytes(*b"2PSB");
+
+/// File format, told apart by the version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BspFormat {
+    /// Quake
+    Bsp29,
+    /// GoldSrc
+    Bsp30,
+    /// Quake with 32-bit indices and float bounds
+    Bsp2,
+    /// Quake with 32-bit indices and short bounds (`2PSB`)
+    Bsp2Rmq,
+}
+
+impl BspFormat {
+    pub fn from_version(version: u32) -> Option<Self> {
+        match version {
+            29 => Some(BspFormat::Bsp29),
+            30 => Some(BspFormat::Bsp30),
+            BSP2_VERSION => Some(BspFormat::Bsp2),
+            BSP2_RMQ_VERSION => Some(BspFormat::Bsp2Rmq),
+            _ => None,
+        }
+    }
+
+    /// GoldSrc maps have RGB lighting and a palette per texture, Quake maps
+    /// have monochrome lighting and textures using the game's shared palette
+    pub fn is_goldsrc(self) -> bool {
+        self == BspFormat::Bsp30
+    }
+
+    /// Whether node, face, edge and mark surface indices are 32 bits wide
+    pub fn has_wide_indices(self) -> bool {
+        matches!(self, BspFormat::Bsp2 | BspFormat::Bsp2Rmq)
+    }
+
+    /// Whether node and leaf bounds are stored as floats
+    pub fn has_float_bounds(self) -> bool {
+        self == BspFormat::Bsp2
+    }
+
+    /// Largest texture extents of a lit face, past which the engine fails
+    /// with "Bad surface extents"
+    pub fn max_surface_extents(self) -> i32 {
+        if self.is_goldsrc() {
+            256
+        } else {
+            512
+        }
+    }
+}
+
+/// Lump order of the file header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BspVariant {
+    #[default]
+    Standard,
+    /// Half-Life: Blue Shift, which swaps the entities and planes lumps
+    BlueShift,
+}
+
+impl BspVariant {
+    /// Maps a `LUMP_*` constant to its index in the header
+    pub fn lump_index(self, lump: usize) -> usize {
+        match (self, lump) {
+            (BspVariant::BlueShift, LUMP_ENTITIES) => LUMP_PLANES,
+            (BspVariant::BlueShift, LUMP_PLANES) => LUMP_ENTITIES,
+            _ => lump,
+        }
+    }
+}
 
 #[binread]
 #[derive(Debug)]
-#[br(assert(version == 30))]
 pub struct Bsp {
+    #[br(assert(
+        BspFormat::from_version(version).is_some(),
+        "unsupported bsp version {:#x}",
+        version
+    ))]
     pub version: u32,
 
-    #[br(parse_with = entry_parser_entities)]
+    #[br(calc = BspFormat::from_version(version).unwrap())]
+    pub format: BspFormat,
+
+    #[br(temp)]
+    lumps: [LumpEntry; HEADER_LUMPS],
+
+    #[br(parse_with = variant_parser, args(lumps, format))]
+    pub variant: BspVariant,
+
+    #[br(parse_with = entry_parser_entities, args(lumps[variant.lump_index(LUMP_ENTITIES)]))]
     pub entities: Vec<Entity>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[variant.lump_index(LUMP_PLANES)], format, ()))]
     pub planes: Vec<Plane>,
 
-    #[br(parse_with = entry_parser_textures)]
+    #[br(parse_with = entry_parser_textures, args(lumps[LUMP_TEXTURES], format))]
     pub textures: Vec<MipTexture>,
     // pub tex_offset: u32,
     // pub tex_size: u32,
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_VERTICES], format, ()))]
     pub vertices: Vec<Vec3>,
 
+    #[br(calc = lumps[LUMP_VISIBILITY].offset)]
     pub vis_offset: u32,
+    #[br(calc = lumps[LUMP_VISIBILITY].length)]
     pub vis_size: u32,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_NODES], format, (format,)))]
     pub nodes: Vec<Node>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_TEXINFO], format, ()))]
     pub texture_infos: Vec<TextureInfo>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_FACES], format, (format,)))]
     pub faces: Vec<Face>,
 
-    #[br(parse_with = entry_parser_vec)]
+    /// RGB samples, expanded from monochrome ones for Quake maps
+    #[br(parse_with = entry_parser_lighting, args(lumps[LUMP_LIGHTING], format))]
     pub lightmap: Vec<u8>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_CLIPNODES], format, (format,)))]
     pub clip_nodes: Vec<ClipNode>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_LEAVES], format, (format,)))]
     pub leaves: Vec<Leaf>,
 
-    #[br(parse_with = entry_parser_vec)]
-    pub mark_surfaces: Vec<u16>,
+    #[br(parse_with = entry_parser_indices, args(lumps[LUMP_MARKSURFACES], format))]
+    pub mark_surfaces: Vec<u32>,
 
-    #[br(parse_with = entry_parser_vec_range)]
-    pub edges: Vec<Range<u16>>,
+    #[br(parse_with = entry_parser_vec_range, args(lumps[LUMP_EDGES], format))]
+    pub edges: Vec<Range<u32>>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_SURFEDGES], format, ()))]
     pub surf_edges: Vec<i32>,
 
-    #[br(parse_with = entry_parser_vec)]
+    #[br(parse_with = entry_parser_vec, args(lumps[LUMP_MODELS], format, ()))]
     pub models: Vec<Model>,
 }
 
@@ -70,6 +238,121 @@ impl Bsp {
     pub fn normals(&self) -> Vec<Vec3> {
         self.planes.iter().map(|p| p.normal).collect()
     }
+
+    /// Gives Quake textures, which have no palette of their own, the game's
+    /// shared palette, see [read_palette]
+    pub fn apply_palette(&mut self, palette: &[Rgb]) {
+        for texture in &mut self.textures {
+            if texture.palette.is_empty() && !texture.indices.is_empty() {
+                texture.palette = palette.to_vec();
+            }
+        }
+    }
+}
+
+/// Reads a Quake palette like `gfx/palette.lmp`, 256 RGB colors
+pub fn read_palette(bytes: &[u8]) -> Vec<Rgb> {
+    bytes
+        .chunks_exact(3)
+        .take(256)
+        .map(|c| Rgb {
+            r: c[0],
+            g: c[1],
+            b: c[2],
+        })
+        .collect()
+}
+
+/// A fixed size element of a lump
+pub trait LumpRecord {
+    /// Size of the element in the file, which differs from its size in
+    /// memory because of padding, enum representations and widened fields
+    fn disk_size(format: BspFormat) -> usize;
+}
+
+impl LumpRecord for u8 {
+    fn disk_size(_: BspFormat) -> usize {
+        1
+    }
+}
+
+impl LumpRecord for i32 {
+    fn disk_size(_: BspFormat) -> usize {
+        4
+    }
+}
+
+impl LumpRecord for Vec3 {
+    fn disk_size(_: BspFormat) -> usize {
+        12
+    }
+}
+
+impl LumpRecord for Plane {
+    fn disk_size(_: BspFormat) -> usize {
+        20
+    }
+}
+
+impl LumpRecord for Node {
+    fn disk_size(format: BspFormat) -> usize {
+        match format {
+            BspFormat::Bsp29 | BspFormat::Bsp30 => 24,
+            BspFormat::Bsp2Rmq => 32,
+            BspFormat::Bsp2 => 44,
+        }
+    }
+}
+
+impl LumpRecord for TextureInfo {
+    fn disk_size(_: BspFormat) -> usize {
+        40
+    }
+}
+
+impl LumpRecord for Face {
+    fn disk_size(format: BspFormat) -> usize {
+        if format.has_wide_indices() {
+            28
+        } else {
+            20
+        }
+    }
+}
+
+impl LumpRecord for ClipNode {
+    fn disk_size(format: BspFormat) -> usize {
+        if format.has_wide_indices() {
+            12
+        } else {
+            8
+        }
+    }
+}
+
+impl LumpRecord for Leaf {
+    fn disk_size(format: BspFormat) -> usize {
+        match format {
+            BspFormat::Bsp29 | BspFormat::Bsp30 => 28,
+            BspFormat::Bsp2Rmq => 32,
+            BspFormat::Bsp2 => 44,
+        }
+    }
+}
+
+impl LumpRecord for Model {
+    fn disk_size(_: BspFormat) -> usize {
+        64
+    }
+}
+
+/// Size of a mark surface, or of one of the two vertex indices of an edge
+pub fn index_disk_size(format: BspFormat) -> usize {
+    if format.has_wide_indices() {
+        4
+    } else {
+        2
+    }
 }
 
 #[binread]
@@ -96,18 +379,27 @@ pub enum PlaneType {
 
 #[binread]
 #[derive(Debug)]
+#[br(import(format: BspFormat))]
 pub struct Node {
     /// Index into planes lump
     pub plane_index: u32,
-    pub children: [i16; 2],
-    pub mins: [i16; 3],
-    pub maxs: [i16; 3],
+    #[br(parse_with = parse_i32s, args(format.has_wide_indices()))]
+    pub children: [i32; 2],
+    #[br(parse_with = parse_bounds, args(format.has_float_bounds()))]
+    pub mins: [f32; 3],
+    #[br(parse_with = parse_bounds, args(format.has_float_bounds()))]
+    pub maxs: [f32; 3],
     /// Index into faces
-    pub first_face: u16,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub first_face: u32,
     /// Count into faces
-    pub num_faces: u16,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub num_faces: u32,
 }
 
+/// [TextureInfo] flag of sky and liquid surfaces, which have no lightmap
+pub const TEX_SPECIAL: u32 = 1;
+
 #[binread]
 #[derive(Debug)]
 pub struct TextureInfo {
@@ -121,32 +413,52 @@ pub struct TextureInfo {
 
 #[binread]
 #[derive(Debug)]
+#[br(import(format: BspFormat))]
 pub struct Face {
-    pub plane_index: u16,
-    pub plane_side: u16,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub plane_index: u32,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub plane_side: u32,
     pub first_edge: u32,
-    pub edges: u16,
-    pub texture_info: u16,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub edges: u32,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub texture_info: u32,
     pub styles: [u8; 4],
+    /// Byte offset into the RGB lightmap
+    #[br(map = |offset: u32| match offset {
+        u32::MAX => offset,
+        _ if format.is_goldsrc() => offset,
+        // Monochrome lighting is expanded to RGB, offsets past any
+        // possible lump are treated as missing
+        _ => offset.saturating_mul(3),
+    })]
     pub lightmap_offset: u32,
 }
 
 #[binread]
 #[derive(Debug)]
+#[br(import(format: BspFormat))]
 pub struct ClipNode {
     pub plane_index: i32,
-    pub children: [i16; 2],
+    #[br(parse_with = parse_i32s, args(format.has_wide_indices()))]
+    pub children: [i32; 2],
 }
 
 #[binread]
 #[derive(Debug)]
+#[br(import(format: BspFormat))]
 pub struct Leaf {
     pub contents: LeafContent,
     pub vis_offset: i32,
-    pub mins: [i16; 3],
-    pub maxs: [i16; 3],
-    pub first_mark_surface: u16,
-    pub mark_surface: u16,
+    #[br(parse_with = parse_bounds, args(format.has_float_bounds()))]
+    pub mins: [f32; 3],
+    #[br(parse_with = parse_bounds, args(format.has_float_bounds()))]
+    pub maxs: [f32; 3],
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub first_mark_surface: u32,
+    #[br(parse_with = parse_u32, args(format.has_wide_indices()))]
+    pub mark_surface: u32,
     pub ambient_levels: [u8; 4],
 }
 
@@ -182,10 +494,38 @@ pub struct Model {
     pub faces: i32,
 }
 
+/// Tells the lump orders apart by looking for the entity text
+#[binrw::parser(reader)]
+fn variant_parser(lumps: [LumpEntry; HEADER_LUMPS], format: BspFormat) -> BinResult<BspVariant> {
+    if !format.is_goldsrc() {
+        return Ok(BspVariant::Standard);
+    }
+
+    let start_pos = reader.stream_position()?;
+
+    let mut is_text = |lump: LumpEntry| -> BinResult<bool> {
+        reader.seek(SeekFrom::Start(lump.offset as u64))?;
+        let mut bytes = vec![0; lump.length.min(64) as usize];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{'))
+    };
+    let variant = if !is_text(lumps[LUMP_ENTITIES])? && is_text(lumps[LUMP_PLANES])? {
+        BspVariant::BlueShift
+    } else {
+        BspVariant::Standard
+    };
+
+    reader.seek(SeekFrom::Start(start_pos))?;
+
+    Ok(variant)
+}
+
 #[binrw::parser(reader, endian)]
-pub fn entry_parser_entities() -> BinResult<Vec<Entity>> {
-    let offset = u32::read_options(reader, endian, ())?;
-    let size = u32::read_options(reader, endian, ())?;
+pub fn entry_parser_entities(lump: LumpEntry) -> BinResult<Vec<Entity>> {
+    let LumpEntry {
+        offset,
+        length: size,
+    } = lump;
 
     let mut values = Vec::new();
     let start_pos = reader.stream_position()?;
@@ -200,24 +540,27 @@ pub fn entry_parser_entities() -> BinResult<Vec<Entity>> {
 
     reader.seek(SeekFrom::Start(start_pos))?;
 
-    let string = String::from_utf8(values).expect("Couldn't parse string");
+    let string = String::from_utf8_lossy(&values);
 
-    Ok(parse_entities(&string))
+    parse_entities(&string).map_err(|err| binrw::Error::Custom {
+        pos: offset as u64,
+        err: Box::new(err),
+    })
 }
 
 #[binrw::parser(reader, endian)]
-fn entry_parser_vec_range() -> BinResult<Vec<Range<u16>>> {
-    let offset = u32::read_options(reader, endian, ())?;
-    let size = u32::read_options(reader, endian, ())?;
+fn entry_parser_vec_range(lump: LumpEntry, format: BspFormat) -> BinResult<Vec<Range<u32>>> {
+    let wide = format.has_wide_indices();
+    let count = record_count(lump, 2 * index_disk_size(format))?;
 
     let mut map = Vec::new();
     let start_pos = reader.stream_position()?;
-    reader.seek(SeekFrom::Start(offset as u64))?;
+    reader.seek(SeekFrom::Start(lump.offset as u64))?;
 
-    for _ in 0..size as usize / size_of::<Range<u16>>() {
+    for _ in 0..count {
         map.push(Range {
-            start: <_>::read_options(reader, endian, ())?,
-            end: <_>::read_options(reader, endian, ())?,
+            start: parse_u32(reader, endian, (wide,))?,
+            end: parse_u32(reader, endian, (wide,))?,
         });
     }
 
@@ -227,9 +570,37 @@ fn entry_parser_vec_range() -> BinResult<Vec<Range<u16>>> {
 }
 
 #[binrw::parser(reader, endian)]
-fn entry_parser_textures() -> BinResult<Vec<MipTexture>> {
-    let start_offset = u32::read_options(reader, endian, ())?;
-    let _size = u32::read_options(reader, endian, ())?;
+fn entry_parser_indices(lump: LumpEntry, format: BspFormat) -> BinResult<Vec<u32>> {
+    let wide = format.has_wide_indices();
+    let count = record_count(lump, index_disk_size(format))?;
+
+    let mut map = Vec::new();
+    let start_pos = reader.stream_position()?;
+    reader.seek(SeekFrom::Start(lump.offset as u64))?;
+
+    for _ in 0..count {
+        map.push(parse_u32(reader, endian, (wide,))?);
+    }
+
+    reader.seek(SeekFrom::Start(start_pos))?;
+
+    Ok(map)
+}
+
+#[binrw::parser(reader, endian)]
+fn entry_parser_lighting(lump: LumpEntry, format: BspFormat) -> BinResult<Vec<u8>> {
+    let samples: Vec<u8> = entry_parser_vec(reader, endian, (lump, format, ()))?;
+
+    if format.is_goldsrc() {
+        Ok(samples)
+    } else {
+        Ok(samples.into_iter().flat_map(|s| [s, s, s]).collect())
+    }
+}
+
+#[binrw::parser(reader, endian)]
+fn entry_parser_textures(lump: LumpEntry, format: BspFormat) -> BinResult<Vec<MipTexture>> {
+    let start_offset = lump.offset;
 
     let start_pos = reader.stream_position()?;
     reader.seek(SeekFrom::Start(start_offset as u64))?;
@@ -243,8 +614,24 @@ fn entry_parser_textures() -> BinResult<Vec<MipTexture>> {
 
     let mut mip_textures = Vec::new();
     for offset in offsets {
-        reader.seek(SeekFrom::Start((start_offset + offset) as u64))?;
-        mip_textures.push(<_>::read_options(reader, endian, ())?);
+        // Textures that failed to load while compiling are left out
+        if offset == u32::MAX {
+            mip_textures.push(MipTexture {
+                name: String::new(),
+                width: 0,
+                height: 0,
+                indices: Vec::new(),
+                palette: Vec::new(),
+            });
+            continue;
+        }
+
+        reader.seek(SeekFrom::Start(start_offset as u64 + offset as u64))?;
+        if format.is_goldsrc() {
+            mip_textures.push(<_>::read_options(reader, endian, ())?);
+        } else {
+            mip_textures.push(quake_mip_texture_parser(reader, endian, ())?);
+        }
     }
 
     reader.seek(SeekFrom::Start(start_pos))?;
@@ -252,17 +639,60 @@ fn entry_parser_textures() -> BinResult<Vec<MipTexture>> {
     Ok(mip_textures)
 }
 
+/// Reads a Quake texture, which unlike GoldSrc ones has no palette
+#[binrw::parser(reader, endian)]
+fn quake_mip_texture_parser() -> BinResult<MipTexture> {
+    let begin = reader.stream_position()?;
+    let name = parse_string(reader, endian, (16,))?;
+    let width = u32::read_options(reader, endian, ())?;
+    let height = u32::read_options(reader, endian, ())?;
+    let offsets = <[u32; 4]>::read_options(reader, endian, ())?;
+
+    let mut indices = Vec::new();
+    if offsets.iter().all(|&x| x != 0) {
+        for (i, offset) in offsets.iter().enumerate() {
+            reader.seek(SeekFrom::Start(begin + *offset as u64))?;
+            let mut buf = vec![0; (width * height) as usize >> (2 * i)];
+            reader.read_exact(&mut buf)?;
+            indices.push(buf);
+        }
+    }
+
+    Ok(MipTexture {
+        name,
+        width,
+        height,
+        indices,
+        palette: Vec::new(),
+    })
+}
+
 #[binrw::parser(reader, endian)]
-fn entry_parser_vec<T: for<'a> BinRead<Args<'a> = ()> + 'static>() -> BinResult<Vec<T>> {
-    let offset = u32::read_options(reader, endian, ())?;
-    let size = u32::read_options(reader, endian, ())?;
+fn entry_parser_vec<T: for<'a> BinRead<Args<'a> = A> + LumpRecord + 'static, A: Clone + 'static>(
+    lump: LumpEntry,
+    format: BspFormat,
+    args: A,
+) -> BinResult<Vec<T>> {
+    let count = record_count(lump, T::disk_size(format))?;
 
     let mut map = Vec::new();
     let start_pos = reader.stream_position()?;
-    reader.seek(SeekFrom::Start(offset as u64))?;
+    reader.seek(SeekFrom::Start(lump.offset as u64))?;
+
+    for _ in 0..count {
+        map.push(<_>::read_options(reader, endian, args.clone())?);
+    }
 
-    for _ in 0..size as usize / size_of::<T>() {
-        map.push(<_>::read_options(reader, endian, ())?);
+    let end = lump.offset as u64 + lump.length as u64;
+    if reader.stream_position()? != end {
+        return Err(binrw::Error::AssertFail {
+            pos: lump.offset as u64,
+            message: format!(
+                "read {} bytes of records instead of {}",
+                reader.stream_position()? - lump.offset as u64,
+                lump.length
+            ),
+        });
     }
 
     reader.seek(SeekFrom::Start(start_pos))?;
@@ -270,6 +700,62 @@ fn entry_parser_vec<T: for<'a> BinRead<Args<'a> = ()> + 'static>() -> BinResult<
     Ok(map)
 }
 
+/// Returns the number of records in a lump, failing when its length isn't a
+/// multiple of the record size
+fn record_count(lump: LumpEntry, record_size: usize) -> BinResult<usize> {
+    if !(lump.length as usize).is_multiple_of(record_size) {
+        return Err(binrw::Error::AssertFail {
+            pos: lump.offset as u64,
+            message: format!(
+                "lump length {} is not a multiple of the {} byte record size",
+                lump.length, record_size
+            ),
+        });
+    }
+
+    Ok(lump.length as usize / record_size)
+}
+
+/// Reads `N` values stored as `i16`, or as `i32` when `wide`
+fn parse_i32s<R: Read + Seek, const N: usize>(
+    reader: &mut R,
+    endian: Endian,
+    (wide,): (bool,),
+) -> BinResult<[i32; N]> {
+    let mut values = [0; N];
+    for value in &mut values {
+        *value = if wide {
+            i32::read_options(reader, endian, ())?
+        } else {
+            i16::read_options(reader, endian, ())? as i32
+        };
+    }
+    Ok(values)
+}
+
+/// Reads a value stored as `u16`, or as `u32` when `wide`
+#[binrw::parser(reader, endian)]
+fn parse_u32(wide: bool) -> BinResult<u32> {
+    if wide {
+        u32::read_options(reader, endian, ())
+    } else {
+        u16::read_options(reader, endian, ()).map(u32::from)
+    }
+}
+
+/// Reads bounds stored as `i16`, or as `f32` when `float`
+fn parse_bounds<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+    (float,): (bool,),
+) -> BinResult<[f32; 3]> {
+    if float {
+        <[f32; 3]>::read_options(reader, endian, ())
+    } else {
+        <[i16; 3]>::read_options(reader, endian, ()).map(|v| v.map(f32::from))
+    }
+}
+
 #[inline]
 pub fn read_bsp(bytes: &[u8]) -> BinResult<Bsp> {
     let mut reader = Cursor::new(bytes);
@@ -0,0 +1,193 @@
+use crate::{Bsp, Face, Rgb};
+
+/// Maximum number of light styles a face can be lit by
+pub const MAX_LIGHTMAPS: usize = 4;
+
+/// Light style slot value for an unused style
+pub const STYLE_NONE: u8 = 255;
+
+/// Lightmap samples of a single face
+#[derive(Debug)]
+pub struct FaceLightmap {
+    pub width: u32,
+    pub height: u32,
+    /// Samples for each of the face's light styles, in [Face::styles] order
+    pub styles: Vec<LightmapStyle>,
+}
+
+#[derive(Debug)]
+pub struct LightmapStyle {
+    /// The light style index
+    pub style: u8,
+    /// `width * height` samples, row by row
+    pub samples: Vec<Rgb>,
+}
+
+/// Location of a face's lightmap inside a [LightmapAtlas]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// All face lightmaps packed into a single RGBA image, each surrounded by a
+/// 1 texel gutter repeating its edge samples so bilinear filtering doesn't
+/// bleed between neighbors
+#[derive(Debug)]
+pub struct LightmapAtlas {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA pixels, row by row
+    pub pixels: Vec<u8>,
+    /// Rectangle of each face, `None` for faces without a lightmap
+    pub rects: Vec<Option<AtlasRect>>,
+}
+
+impl LightmapAtlas {
+    /// Converts a [MeshVertex::lightmap_uv](crate::MeshVertex) of a face into atlas coordinates
+    pub fn uv(&self, face: usize, lightmap_uv: [f32; 2]) -> Option<[f32; 2]> {
+        let rect = self.rects.get(face).copied().flatten()?;
+        Some([
+            (rect.x as f32 + lightmap_uv[0] * rect.width as f32) / self.width as f32,
+            (rect.y as f32 + lightmap_uv[1] * rect.height as f32) / self.height as f32,
+        ])
+    }
+
+    /// Overwrites the pixels of a face and its gutter with new samples
+    pub fn write(&mut self, face: usize, samples: &[Rgb]) {
+        let Some(rect) = self.rects.get(face).copied().flatten() else {
+            return;
+        };
+        if samples.len() < (rect.width * rect.height) as usize {
+            return;
+        }
+
+        for y in rect.y - 1..=rect.y + rect.height {
+            for x in rect.x - 1..=rect.x + rect.width {
+                let sample_x = x.clamp(rect.x, rect.x + rect.width - 1) - rect.x;
+                let sample_y = y.clamp(rect.y, rect.y + rect.height - 1) - rect.y;
+                let sample = samples[(sample_y * rect.width + sample_x) as usize];
+                let pixel = ((y * self.width + x) * 4) as usize;
+                self.pixels[pixel..pixel + 4].copy_from_slice(&[sample.r, sample.g, sample.b, 255]);
+            }
+        }
+    }
+}
+
+impl Bsp {
+    /// Returns the lightmap size of a face in samples, one sample covering 16 texels
+    pub fn lightmap_size(&self, face: &Face) -> (u32, u32) {
+        let (_, extents) = self.texture_extents(face);
+        (extents[0] as u32 / 16 + 1, extents[1] as u32 / 16 + 1)
+    }
+
+    /// Extracts the samples of every light style of a face, `None` for faces
+    /// without a lightmap or with extents the engine rejects
+    pub fn face_lightmap(&self, face: &Face) -> Option<FaceLightmap> {
+        if face.lightmap_offset == u32::MAX || face.styles[0] == STYLE_NONE {
+            return None;
+        }
+
+        let (_, extents) = self.texture_extents(face);
+        let max_extents = self.format.max_surface_extents();
+        if extents
+            .iter()
+            .any(|&extent| !(0..=max_extents).contains(&extent))
+        {
+            return None;
+        }
+
+        let (width, height) = self.lightmap_size(face);
+        let size = (width * height) as usize * 3;
+
+        let mut styles = Vec::new();
+        for (i, &style) in face
+            .styles
+            .iter()
+            .take_while(|&&s| s != STYLE_NONE)
+            .enumerate()
+        {
+            let start = face.lightmap_offset as usize + i * size;
+            let bytes = self.lightmap.get(start..start + size)?;
+            styles.push(LightmapStyle {
+                style,
+                samples: bytes
+                    .chunks_exact(3)
+                    .map(|c| Rgb {
+                        r: c[0],
+                        g: c[1],
+                        b: c[2],
+                    })
+                    .collect(),
+            });
+        }
+
+        Some(FaceLightmap {
+            width,
+            height,
+            styles,
+        })
+    }
+
+    /// Packs the first light style of every face into a single atlas
+    pub fn lightmap_atlas(&self) -> LightmapAtlas {
+        let lightmaps: Vec<_> = self.faces.iter().map(|f| self.face_lightmap(f)).collect();
+
+        let area: u32 = lightmaps
+            .iter()
+            .flatten()
+            .map(|l| (l.width + 2) * (l.height + 2))
+            .sum();
+        let widest = lightmaps
+            .iter()
+            .flatten()
+            .map(|l| l.width + 2)
+            .max()
+            .unwrap_or(1);
+        let width = ((area as f32).sqrt() as u32)
+            .max(widest)
+            .next_power_of_two();
+
+        // Shelf packing, tallest lightmaps first
+        let mut order: Vec<_> = (0..lightmaps.len())
+            .filter(|&i| lightmaps[i].is_some())
+            .collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(lightmaps[i].as_ref().unwrap().height));
+
+        let mut rects = vec![None; lightmaps.len()];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for i in order {
+            let lightmap = lightmaps[i].as_ref().unwrap();
+            if x + lightmap.width + 2 > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            rects[i] = Some(AtlasRect {
+                x: x + 1,
+                y: y + 1,
+                width: lightmap.width,
+                height: lightmap.height,
+            });
+            x += lightmap.width + 2;
+            shelf_height = shelf_height.max(lightmap.height + 2);
+        }
+        let height = (y + shelf_height).max(1).next_power_of_two();
+
+        let mut atlas = LightmapAtlas {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            rects,
+        };
+        for (i, lightmap) in lightmaps.iter().enumerate() {
+            if let Some(style) = lightmap.as_ref().and_then(|l| l.styles.first()) {
+                atlas.write(i, &style.samples);
+            }
+        }
+
+        atlas
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{Bsp, Entity, Face, Rgb};
+
+/// Maximum number of light styles
+pub const MAX_LIGHTSTYLES: usize = 64;
+
+/// First light style that can be switched on and off by `light` entities
+pub const FIRST_SWITCHABLE_STYLE: u8 = 32;
+
+/// Light style patterns the game dll sets up in `worldspawn`
+pub const DEFAULT_LIGHT_STYLES: [(u8, &str); 14] = [
+    // Normal
+    (0, "m"),
+    // Flicker (first variety)
+    (1, "mmnmmommommnonmmonqnmmo"),
+    // Slow strong pulse
+    (2, "abcdefghijklmnopqrstuvwxyzyxwvutsrqponmlkjihgfedcba"),
+    // Candle (first variety)
+    (3, "mmmmmaaaaammmmmaaaaaabcdefgabcdefg"),
+    // Fast strobe
+    (4, "mamamamamama"),
+    // Gentle pulse
+    (5, "jklmnopqrstuvwxyzyxwvutsrqponmlkj"),
+    // Flicker (second variety)
+    (6, "nmonqnmomnmomomno"),
+    // Candle (second variety)
+    (7, "mmmaaaabcdefgmmmmaaaammmaamm"),
+    // Candle (third variety)
+    (8, "mmmaaammmaaammmabcdefaaaammmmabcdefmmmaaaa"),
+    // Slow strobe
+    (9, "aaaaaaaazzzzzzzz"),
+    // Fluorescent flicker
+    (10, "mmamammmmammamamaaamammma"),
+    // Slow pulse not fading to black
+    (11, "abcdefghijklmnopqrrqponmlkjihgfedcba"),
+    // Underwater light mutation
+    (12, "mmnnmmnnnmmnn"),
+    // Testing
+    (63, "a"),
+];
+
+/// Frames per second of light style patterns
+pub const LIGHT_STYLE_FPS: f32 = 10.0;
+
+/// Brightness the engine uses for styles without a pattern, just below the
+/// 264 of "m"
+pub const NORMAL_LIGHT_STYLE_VALUE: u32 = 256;
+
+/// `light` spawnflag keeping a switchable light off at map start
+const SF_LIGHT_START_OFF: u32 = 1;
+
+/// Light style patterns, indexed by style
+#[derive(Debug, Clone)]
+pub struct LightStyles {
+    pub patterns: Vec<String>,
+}
+
+impl Default for LightStyles {
+    fn default() -> Self {
+        let mut patterns = vec![String::new(); MAX_LIGHTSTYLES];
+        for (style, pattern) in DEFAULT_LIGHT_STYLES {
+            patterns[style as usize] = pattern.to_string();
+        }
+        Self { patterns }
+    }
+}
+
+impl LightStyles {
+    /// Default patterns with the switchable styles of `light` entities applied
+    pub fn from_entities(entities: &[Entity]) -> Self {
+        let mut styles = Self::default();
+
+        for entity in entities {
+            if !matches!(entity.classname(), Some("light" | "light_spot")) {
+                continue;
+            }
+            let Some(style) = entity.get_i32("style") else {
+                continue;
+            };
+            if style < FIRST_SWITCHABLE_STYLE as i32 || style >= MAX_LIGHTSTYLES as i32 {
+                continue;
+            }
+
+            let spawnflags = entity.get_i32("spawnflags").unwrap_or(0) as u32;
+            let pattern = if spawnflags & SF_LIGHT_START_OFF != 0 {
+                "a"
+            } else {
+                entity.get("pattern").unwrap_or("m")
+            };
+            styles.set(style as u8, pattern);
+        }
+
+        styles
+    }
+
+    pub fn set(&mut self, style: u8, pattern: impl Into<String>) {
+        if let Some(slot) = self.patterns.get_mut(style as usize) {
+            *slot = pattern.into();
+        }
+    }
+
+    /// Returns the brightness of a style at a given time, 0 being "a" and 550
+    /// the maximum ("z"), or [NORMAL_LIGHT_STYLE_VALUE] without a pattern
+    pub fn value(&self, style: u8, time: f32) -> u32 {
+        let pattern = match self.patterns.get(style as usize) {
+            Some(pattern) if !pattern.is_empty() => pattern.as_bytes(),
+            _ => return NORMAL_LIGHT_STYLE_VALUE,
+        };
+
+        let frame = (time * LIGHT_STYLE_FPS).max(0.0) as usize % pattern.len();
+        pattern[frame].saturating_sub(b'a') as u32 * 22
+    }
+}
+
+impl Bsp {
+    /// Light styles of this map, see [LightStyles::from_entities]
+    pub fn light_styles(&self) -> LightStyles {
+        LightStyles::from_entities(&self.entities)
+    }
+
+    /// Sums the light styles of a face, scaled by their brightness at a given time
+    pub fn compose_lightmap(
+        &self,
+        face: &Face,
+        styles: &LightStyles,
+        time: f32,
+    ) -> Option<Vec<Rgb>> {
+        let lightmap = self.face_lightmap(face)?;
+
+        let mut sums = vec![[0u32; 3]; (lightmap.width * lightmap.height) as usize];
+        for style in &lightmap.styles {
+            let scale = styles.value(style.style, time);
+            for (sum, sample) in sums.iter_mut().zip(&style.samples) {
+                sum[0] += sample.r as u32 * scale;
+                sum[1] += sample.g as u32 * scale;
+                sum[2] += sample.b as u32 * scale;
+            }
+        }
+
+        Some(
+            sums.into_iter()
+                .map(|[r, g, b]| Rgb {
+                    r: (r >> 8).min(255) as u8,
+                    g: (g >> 8).min(255) as u8,
+                    b: (b >> 8).min(255) as u8,
+                })
+                .collect(),
+        )
+    }
+}
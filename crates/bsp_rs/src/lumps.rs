@@ -0,0 +1,121 @@
+use std::io::Cursor;
+
+use binrw::prelude::*;
+
+use crate::{LumpEntry, HEADER_LUMPS};
+
+/// Raw view of a BSP file's lumps, for reading or patching lumps [Bsp](crate::Bsp)
+/// doesn't model, or models lossily
+///
+/// Lumps are indexed by their position in the header, use
+/// [BspVariant::lump_index](crate::BspVariant::lump_index) to map `LUMP_*`
+/// constants for Blue Shift maps.
+#[derive(Debug, Clone)]
+pub struct BspLumps<'a> {
+    bytes: &'a [u8],
+    pub version: u32,
+    /// The lump directory as read from the file
+    pub entries: [LumpEntry; HEADER_LUMPS],
+    replaced: [Option<Vec<u8>>; HEADER_LUMPS],
+}
+
+impl<'a> BspLumps<'a> {
+    /// Returns the contents of a lump, or the data it was replaced with
+    pub fn lump(&self, index: usize) -> &[u8] {
+        match &self.replaced[index] {
+            Some(data) => data,
+            None => {
+                let LumpEntry { offset, length } = self.entries[index];
+                &self.bytes[offset as usize..(offset + length) as usize]
+            }
+        }
+    }
+
+    /// Sets the contents of a lump, written out by [BspLumps::write]
+    pub fn replace(&mut self, index: usize, data: Vec<u8>) {
+        self.replaced[index] = Some(data);
+    }
+
+    /// Data after the last lump, like a BSPX extension
+    pub fn trailing(&self) -> &[u8] {
+        let end = self
+            .entries
+            .iter()
+            .map(|entry| (entry.offset + entry.length) as usize)
+            .max()
+            .unwrap_or(0)
+            .max(4 + HEADER_LUMPS * 8);
+        // Lumps are 4 byte aligned
+        &self.bytes[((end + 3) & !3).min(self.bytes.len())..]
+    }
+
+    /// Rebuilds the file, keeping the lumps in their original order followed
+    /// by the [trailing](BspLumps::trailing) data
+    ///
+    /// BSPX lump offsets are moved along with the BSPX header, other trailing
+    /// data is copied as is. Gaps between lumps aren't kept.
+    pub fn write(&self) -> Vec<u8> {
+        let header_size = 4 + HEADER_LUMPS * 8;
+
+        let mut order: Vec<_> = (0..HEADER_LUMPS).collect();
+        order.sort_by_key(|&i| self.entries[i].offset);
+
+        let mut out = self.bytes[..header_size].to_vec();
+        for i in order {
+            let contents = self.lump(i);
+
+            let offset = out.len() as u32;
+            out[4 + i * 8..8 + i * 8].copy_from_slice(&offset.to_le_bytes());
+            out[8 + i * 8..12 + i * 8].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+            out.extend_from_slice(contents);
+            // Lumps are 4 byte aligned
+            out.resize((out.len() + 3) & !3, 0);
+        }
+
+        let trailing = self.trailing();
+        let moved_by = out.len() as i64 - (self.bytes.len() - trailing.len()) as i64;
+        let start = out.len();
+        out.extend_from_slice(trailing);
+        relocate_bspx(&mut out[start..], moved_by);
+
+        out
+    }
+}
+
+/// Shifts the file offsets of a BSPX lump directory, if `data` starts with one
+fn relocate_bspx(data: &mut [u8], moved_by: i64) {
+    if !data.starts_with(b"BSPX") || data.len() < 8 {
+        return;
+    }
+
+    let lumps = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+    // Name, offset and length of each lump
+    for entry in data[8..].chunks_exact_mut(32).take(lumps) {
+        let offset = u32::from_le_bytes(entry[24..28].try_into().unwrap());
+        let offset = (offset as i64 + moved_by) as u32;
+        entry[24..28].copy_from_slice(&offset.to_le_bytes());
+    }
+}
+
+/// Reads the header of a BSP file, checking every lump lies within it
+pub fn read_bsp_lumps(bytes: &[u8]) -> BinResult<BspLumps<'_>> {
+    let mut reader = Cursor::new(bytes);
+    let version = reader.read_le()?;
+    let entries: [LumpEntry; HEADER_LUMPS] = reader.read_le()?;
+
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.offset as u64 + entry.length as u64 > bytes.len() as u64 {
+            return Err(binrw::Error::AssertFail {
+                pos: 4 + i as u64 * 8,
+                message: format!("lump {} is out of bounds", i),
+            });
+        }
+    }
+
+    Ok(BspLumps {
+        bytes,
+        version,
+        entries,
+        replaced: Default::default(),
+    })
+}
@@ -0,0 +1,203 @@
+use crate::{Bsp, Face, Vec3};
+
+/// A single vertex of a triangulated face
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshVertex {
+    pub position: Vec3,
+    /// Face normal, flipped when the face is on the back of its plane
+    pub normal: Vec3,
+    /// Texture coordinates, normalized by the texture size
+    pub uv: [f32; 2],
+    /// Lightmap coordinates, normalized to the face's own lightmap
+    pub lightmap_uv: [f32; 2],
+    /// Index into faces
+    pub face: u32,
+}
+
+/// Triangles of a model sharing the same texture
+#[derive(Debug)]
+pub struct MeshGroup {
+    /// Index into textures
+    pub texture: u32,
+    pub vertices: Vec<MeshVertex>,
+    /// Triangle list, clockwise when seen from the front like the engine's
+    /// faces. glTF, OBJ and OpenGL treat counter-clockwise as the front, see
+    /// [MeshGroup::counter_clockwise_indices].
+    pub indices: Vec<u32>,
+}
+
+impl MeshGroup {
+    /// Returns the triangle list with every triangle reversed, for formats
+    /// whose front faces are counter-clockwise
+    pub fn counter_clockwise_indices(&self) -> Vec<u32> {
+        self.indices
+            .chunks_exact(3)
+            .flat_map(|triangle| [triangle[0], triangle[2], triangle[1]])
+            .collect()
+    }
+}
+
+/// Render mesh of a single [Model](crate::Model), grouped by texture
+#[derive(Debug, Default)]
+pub struct ModelMesh {
+    pub groups: Vec<MeshGroup>,
+}
+
+impl Bsp {
+    /// Returns the world positions of a face's vertices in winding order
+    ///
+    /// Yields nothing when the face references a missing surfedge, edge or
+    /// vertex.
+    pub fn face_vertices<'a>(&'a self, face: &Face) -> impl Iterator<Item = Vec3> + 'a {
+        let first = face.first_edge as usize;
+        let positions = first
+            .checked_add(face.edges as usize)
+            .and_then(|end| self.surf_edges.get(first..end))
+            .and_then(|surf_edges| {
+                surf_edges
+                    .iter()
+                    .map(|&surf_edge| {
+                        let edge = self.edges.get(surf_edge.unsigned_abs() as usize)?;
+                        let vertex = if surf_edge >= 0 { edge.start } else { edge.end };
+                        self.vertices.get(vertex as usize).copied()
+                    })
+                    .collect::<Option<Vec<_>>>()
+            });
+
+        positions.unwrap_or_default().into_iter()
+    }
+
+    /// Returns the normal of a face, taking [Face::plane_side] into account
+    ///
+    /// Faces referencing a missing plane have a zero normal.
+    pub fn face_normal(&self, face: &Face) -> Vec3 {
+        let normal = self
+            .planes
+            .get(face.plane_index as usize)
+            .map_or(Vec3::new(0.0, 0.0, 0.0), |plane| plane.normal);
+        if face.plane_side != 0 {
+            -normal
+        } else {
+            normal
+        }
+    }
+
+    /// Triangulates every face of a model
+    ///
+    /// Faces with fewer than 3 vertices or referencing missing records are
+    /// skipped, as is a missing model.
+    pub fn model_mesh(&self, model: usize) -> ModelMesh {
+        let mut mesh = ModelMesh::default();
+        let Some(model) = self.models.get(model) else {
+            return mesh;
+        };
+
+        let first = model.first_face.max(0) as usize;
+        for face_index in first..first + model.faces.max(0) as usize {
+            let Some(face) = self.faces.get(face_index) else {
+                break;
+            };
+            let Some(texture_info) = self.texture_infos.get(face.texture_info as usize) else {
+                continue;
+            };
+            let positions: Vec<_> = self.face_vertices(face).collect();
+            if positions.len() < 3 || self.planes.get(face.plane_index as usize).is_none() {
+                continue;
+            }
+
+            let group = match mesh
+                .groups
+                .iter()
+                .position(|g| g.texture == texture_info.miptex)
+            {
+                Some(i) => &mut mesh.groups[i],
+                None => {
+                    mesh.groups.push(MeshGroup {
+                        texture: texture_info.miptex,
+                        vertices: Vec::new(),
+                        indices: Vec::new(),
+                    });
+                    mesh.groups.last_mut().unwrap()
+                }
+            };
+
+            let (width, height) = self
+                .textures
+                .get(texture_info.miptex as usize)
+                .map(|t| (t.width.max(1) as f32, t.height.max(1) as f32))
+                .unwrap_or((1.0, 1.0));
+            let (lightmap_mins, lightmap_extents) = self.texture_extents(face);
+            let normal = self.face_normal(face);
+
+            let base = group.vertices.len() as u32;
+            for &position in &positions {
+                let s = position.dot(texture_info.s) + texture_info.s_shift;
+                let t = position.dot(texture_info.t) + texture_info.t_shift;
+                group.vertices.push(MeshVertex {
+                    position,
+                    normal,
+                    uv: [s / width, t / height],
+                    lightmap_uv: [
+                        (s - lightmap_mins[0] as f32 + 8.0) / (lightmap_extents[0] + 16) as f32,
+                        (t - lightmap_mins[1] as f32 + 8.0) / (lightmap_extents[1] + 16) as f32,
+                    ],
+                    face: face_index as u32,
+                });
+            }
+
+            for i in 1..positions.len() as u32 - 1 {
+                group.indices.extend([base, base + i, base + i + 1]);
+            }
+        }
+
+        mesh
+    }
+
+    /// Triangulates every model, the world being the first one
+    pub fn meshes(&self) -> Vec<ModelMesh> {
+        (0..self.models.len()).map(|i| self.model_mesh(i)).collect()
+    }
+
+    /// Computes the texture space mins and extents of a face, snapped to
+    /// the 16 texel lightmap grid like the engine's `CalcSurfaceExtents`
+    ///
+    /// Faces without a texture info or vertices have empty extents.
+    pub(crate) fn texture_extents(&self, face: &Face) -> ([i32; 2], [i32; 2]) {
+        let Some(texture_info) = self.texture_infos.get(face.texture_info as usize) else {
+            return ([0; 2], [0; 2]);
+        };
+        let mut mins = [f64::MAX; 2];
+        let mut maxs = [f64::MIN; 2];
+
+        for position in self.face_vertices(face) {
+            for (i, (axis, shift)) in [
+                (texture_info.s, texture_info.s_shift),
+                (texture_info.t, texture_info.t_shift),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let value = position.x as f64 * axis.x as f64
+                    + position.y as f64 * axis.y as f64
+                    + position.z as f64 * axis.z as f64
+                    + shift as f64;
+                mins[i] = mins[i].min(value);
+                maxs[i] = maxs[i].max(value);
+            }
+        }
+
+        let mut texture_mins = [0; 2];
+        let mut extents = [0; 2];
+        if mins[0] > maxs[0] {
+            return (texture_mins, extents);
+        }
+        for i in 0..2 {
+            let min = (mins[i] / 16.0).floor() as i32;
+            let max = (maxs[i] / 16.0).ceil() as i32;
+            texture_mins[i] = min * 16;
+            extents[i] = (max - min) * 16;
+        }
+
+        (texture_mins, extents)
+    }
+}
@@ -0,0 +1,167 @@
+use std::{fmt::Write, fs, io, path::Path};
+
+use crate::{
+    export::{embedded_pixels, encode_png, is_transparent, positive_zero},
+    Bsp, Vec3,
+};
+
+/// Textures of surfaces only compilers care about, matched case-insensitively
+pub const TOOL_TEXTURES: [&str; 7] = [
+    "sky",
+    "clip",
+    "origin",
+    "aaatrigger",
+    "null",
+    "hint",
+    "skip",
+];
+
+/// Up axis of the exported coordinates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpAxis {
+    /// Blender and Maya's default, the engine's Y being flipped into -Z
+    #[default]
+    Y,
+    /// The engine's own axes
+    Z,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjOptions {
+    /// Leaves out faces with one of the [TOOL_TEXTURES]
+    pub skip_tool_textures: bool,
+    pub up_axis: UpAxis,
+    /// Multiplies every coordinate, 0.0254 turns units into meters
+    pub scale: f32,
+}
+
+impl Default for ObjOptions {
+    fn default() -> Self {
+        Self {
+            skip_tool_textures: true,
+            up_axis: UpAxis::default(),
+            scale: 1.0,
+        }
+    }
+}
+
+/// The files making up an OBJ export
+#[derive(Debug, Clone, Default)]
+pub struct ObjExport {
+    pub obj: String,
+    pub mtl: String,
+    /// File names and PNG data of the textures stored in the map
+    pub textures: Vec<(String, Vec<u8>)>,
+}
+
+impl Bsp {
+    /// Exports the map as an OBJ file with one object per model, referencing
+    /// `mtl_name` for its materials
+    ///
+    /// Textures stored in WADs have no pixels in the map, their materials
+    /// are written without an image.
+    pub fn to_obj(&self, mtl_name: &str, options: &ObjOptions) -> ObjExport {
+        let mut export = ObjExport::default();
+        let convert = |v: Vec3| {
+            let v = match options.up_axis {
+                UpAxis::Y => Vec3::new(v.x, v.z, -v.y),
+                UpAxis::Z => v,
+            };
+            positive_zero(v * options.scale)
+        };
+
+        for texture in &self.textures {
+            writeln!(export.mtl, "newmtl {}", texture.name).unwrap();
+            writeln!(export.mtl, "Kd 1 1 1").unwrap();
+
+            let Some(pixels) = embedded_pixels(texture) else {
+                export.mtl.push('\n');
+                continue;
+            };
+
+            let png = encode_png(&pixels, texture.width, texture.height);
+            let file_name = format!("{}.png", file_name(&texture.name));
+            writeln!(export.mtl, "map_Kd {}", file_name).unwrap();
+            if is_transparent(texture) {
+                writeln!(export.mtl, "map_d {}", file_name).unwrap();
+            }
+            export.mtl.push('\n');
+            export.textures.push((file_name, png));
+        }
+
+        writeln!(export.obj, "mtllib {}", mtl_name).unwrap();
+        let mut vertex_count = 0;
+        for (i, mesh) in self.meshes().iter().enumerate() {
+            writeln!(export.obj, "o *{}", i).unwrap();
+
+            for group in &mesh.groups {
+                let texture = self.textures.get(group.texture as usize);
+                let is_tool = texture.is_some_and(|texture| {
+                    TOOL_TEXTURES
+                        .iter()
+                        .any(|tool| texture.name.eq_ignore_ascii_case(tool))
+                });
+                if options.skip_tool_textures && is_tool {
+                    continue;
+                }
+
+                if let Some(texture) = texture {
+                    writeln!(export.obj, "usemtl {}", texture.name).unwrap();
+                }
+                for vertex in &group.vertices {
+                    let p = convert(vertex.position);
+                    let n = convert(vertex.normal).normalize();
+                    writeln!(export.obj, "v {} {} {}", p.x, p.y, p.z).unwrap();
+                    // OBJ texture coordinates start at the bottom
+                    writeln!(export.obj, "vt {} {}", vertex.uv[0], 1.0 - vertex.uv[1]).unwrap();
+                    writeln!(export.obj, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+                }
+                for triangle in group.counter_clockwise_indices().chunks_exact(3) {
+                    export.obj.push('f');
+                    for &index in triangle {
+                        let index = vertex_count + index as usize + 1;
+                        write!(export.obj, " {0}/{0}/{0}", index).unwrap();
+                    }
+                    export.obj.push('\n');
+                }
+                vertex_count += group.vertices.len();
+            }
+        }
+
+        export
+    }
+
+    /// Writes `name.obj`, `name.mtl` and the textures into a directory, see
+    /// [Bsp::to_obj]
+    pub fn export_obj<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        name: &str,
+        options: &ObjOptions,
+    ) -> io::Result<()> {
+        let directory = directory.as_ref();
+        let mtl_name = format!("{}.mtl", name);
+        let export = self.to_obj(&mtl_name, options);
+
+        fs::write(directory.join(format!("{}.obj", name)), export.obj)?;
+        fs::write(directory.join(mtl_name), export.mtl)?;
+        for (file_name, png) in export.textures {
+            fs::write(directory.join(file_name), png)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Makes a texture name safe to use as a file name, replacing the `*` of
+/// liquids with `#` like the Quake tools do
+fn file_name(texture: &str) -> String {
+    texture
+        .chars()
+        .map(|c| match c {
+            '*' => '#',
+            '/' | '\\' | ':' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
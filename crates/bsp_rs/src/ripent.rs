@@ -0,0 +1,100 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    parse_entities, read_bsp, read_bsp_lumps, write_entities, Bsp, EntityParseError,
+    EntityWriteError, LUMP_ENTITIES,
+};
+
+/// Error while importing a `.ent` file into a BSP
+#[derive(Debug)]
+pub enum RipentError {
+    /// The BSP itself couldn't be read
+    Bsp(binrw::Error),
+    /// The `.ent` file has a syntax error
+    Entities(EntityParseError),
+    /// A brush entity references a model the BSP doesn't have
+    InvalidModel {
+        /// Index of the entity in the `.ent` file
+        entity: usize,
+        /// The `model` key value
+        model: String,
+    },
+}
+
+impl fmt::Display for RipentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RipentError::Bsp(err) => write!(f, "couldn't read bsp: {}", err),
+            RipentError::Entities(err) => write!(f, "couldn't parse entities: {}", err),
+            RipentError::InvalidModel { entity, model } => {
+                write!(
+                    f,
+                    "entity {} references missing model \"{}\"",
+                    entity, model
+                )
+            }
+        }
+    }
+}
+
+impl Error for RipentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RipentError::Bsp(err) => Some(err),
+            RipentError::Entities(err) => Some(err),
+            RipentError::InvalidModel { .. } => None,
+        }
+    }
+}
+
+impl From<binrw::Error> for RipentError {
+    fn from(err: binrw::Error) -> Self {
+        RipentError::Bsp(err)
+    }
+}
+
+impl From<EntityParseError> for RipentError {
+    fn from(err: EntityParseError) -> Self {
+        RipentError::Entities(err)
+    }
+}
+
+/// Returns the contents of a `.ent` file for a map, see [write_entities]
+pub fn export_ent(bsp: &Bsp) -> Result<String, EntityWriteError> {
+    write_entities(&bsp.entities)
+}
+
+/// Replaces the entity lump of a BSP file with the contents of a `.ent` file
+///
+/// Brush entity `model` keys (`*N`) must reference one of the BSP's models.
+pub fn import_ent(bytes: &[u8], ent: &str) -> Result<Vec<u8>, RipentError> {
+    let bsp = read_bsp(bytes)?;
+    let entities = parse_entities(ent)?;
+
+    for (i, entity) in entities.iter().enumerate() {
+        let Some(model) = entity.get("model") else {
+            continue;
+        };
+        let Some(index) = model.strip_prefix('*') else {
+            continue;
+        };
+        if !index
+            .parse::<usize>()
+            .is_ok_and(|index| index < bsp.models.len())
+        {
+            return Err(RipentError::InvalidModel {
+                entity: i,
+                model: model.to_string(),
+            });
+        }
+    }
+
+    let mut lump = write_entities(&entities)
+        .expect("parsed values have no double quotes")
+        .into_bytes();
+    lump.push(0);
+
+    let mut lumps = read_bsp_lumps(bytes)?;
+    lumps.replace(bsp.variant.lump_index(LUMP_ENTITIES), lump);
+    Ok(lumps.write())
+}
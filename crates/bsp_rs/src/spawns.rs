@@ -0,0 +1,188 @@
+use crate::{BoundBox, Bsp, LeafContent, Vec3};
+
+/// Hull the engine moves standing players with
+pub const HUMAN_HULL: usize = 1;
+/// Height of a standing player's eyes above their origin in Counter-Strike
+pub const VIEW_HEIGHT: f32 = 17.0;
+/// Brush entities that act like triggers without a `trigger_` classname
+pub const ZONE_CLASSNAMES: [&str; 5] = [
+    "func_buyzone",
+    "func_bomb_target",
+    "func_hostage_rescue",
+    "func_escapezone",
+    "func_vip_safetyzone",
+];
+
+/// Team a spawn point belongs to, Counter-Strike using `info_player_start`
+/// for counter-terrorists and `info_player_deathmatch` for terrorists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnTeam {
+    Terrorist,
+    CounterTerrorist,
+}
+
+impl SpawnTeam {
+    pub fn from_classname(classname: &str) -> Option<Self> {
+        match classname {
+            "info_player_deathmatch" | "info_player_terrorist" => Some(SpawnTeam::Terrorist),
+            "info_player_start" | "info_player_ct" => Some(SpawnTeam::CounterTerrorist),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spawn {
+    /// Index into entities
+    pub entity_index: usize,
+    pub team: SpawnTeam,
+    pub origin: Vec3,
+    /// Whether a player spawning here would be inside the world's
+    /// [HUMAN_HULL], brush entities aren't checked
+    pub stuck: bool,
+}
+
+/// A brush entity's volume
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trigger {
+    /// Index into entities
+    pub entity_index: usize,
+    pub classname: String,
+    pub bounds: BoundBox,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpawnAnalysis {
+    pub spawns: Vec<Spawn>,
+    /// Indices into [SpawnAnalysis::spawns] of opposing spawns whose
+    /// players see each other through the world
+    pub sight_lines: Vec<(usize, usize)>,
+    /// `trigger_` entities and the [ZONE_CLASSNAMES]
+    pub triggers: Vec<Trigger>,
+}
+
+impl Bsp {
+    /// Finds the spawn points and triggers of a map and checks whether
+    /// players could get stuck or see each other when spawning
+    pub fn analyze_spawns(&self) -> SpawnAnalysis {
+        let mut analysis = SpawnAnalysis::default();
+
+        for (entity_index, entity) in self.entities.iter().enumerate() {
+            let Some(team) = entity.classname().and_then(SpawnTeam::from_classname) else {
+                continue;
+            };
+            let Some(origin) = entity.get_vec3("origin") else {
+                continue;
+            };
+            analysis.spawns.push(Spawn {
+                entity_index,
+                team,
+                origin,
+                stuck: self.is_solid(HUMAN_HULL, origin),
+            });
+        }
+
+        let eyes = Vec3::new(0.0, 0.0, VIEW_HEIGHT);
+        for (i, a) in analysis.spawns.iter().enumerate() {
+            for (j, b) in analysis.spawns.iter().enumerate().skip(i + 1) {
+                if a.team != b.team && self.line_of_sight(a.origin + eyes, b.origin + eyes) {
+                    analysis.sight_lines.push((i, j));
+                }
+            }
+        }
+
+        for brush_entity in self.brush_entities() {
+            let Some(classname) = brush_entity.entity.classname() else {
+                continue;
+            };
+            if classname.starts_with("trigger_") || ZONE_CLASSNAMES.contains(&classname) {
+                analysis.triggers.push(Trigger {
+                    entity_index: brush_entity.entity_index,
+                    classname: classname.to_string(),
+                    bounds: brush_entity.bounds(),
+                });
+            }
+        }
+
+        analysis
+    }
+
+    /// Whether a point is inside the world's solid for a hull, like the
+    /// engine's `SV_HullPointContents`
+    ///
+    /// Hull 0 is the visible geometry, hulls 1 to 3 are expanded by the
+    /// standing player, large monster and crouching player boxes.
+    pub fn is_solid(&self, hull: usize, point: Vec3) -> bool {
+        if hull == 0 {
+            return self
+                .leaves
+                .get(self.leaf_at(point))
+                .is_none_or(|leaf| matches!(leaf.contents, LeafContent::Solid | LeafContent::Sky));
+        }
+
+        let Some(&root) = self
+            .models
+            .first()
+            .and_then(|world| world.head_nodes.get(hull))
+        else {
+            return false;
+        };
+        let mut child = root;
+        // A path visits each node at most once, unless a corrupt tree loops
+        for _ in 0..self.clip_nodes.len() {
+            if child < 0 {
+                break;
+            }
+            let Some(node) = self.clip_nodes.get(child as usize) else {
+                return false;
+            };
+            let Some(plane) = self.planes.get(node.plane_index as usize) else {
+                return false;
+            };
+            let side = plane.normal.dot(point) - plane.dist < 0.0;
+            child = node.children[side as usize];
+        }
+
+        child == LeafContent::Solid as i32
+    }
+
+    /// Whether a line crosses no solid or sky leaf of the world
+    pub fn line_of_sight(&self, start: Vec3, end: Vec3) -> bool {
+        let root = self.models.first().map_or(0, |world| world.head_nodes[0]);
+        self.segment_is_clear(root, 0, start, end)
+    }
+
+    /// Splits a segment along the planes it crosses, like the engine's
+    /// `SV_RecursiveHullCheck`, paths deeper than the node count being
+    /// loops of a corrupt tree
+    fn segment_is_clear(&self, child: i32, depth: usize, start: Vec3, end: Vec3) -> bool {
+        if depth > self.nodes.len() {
+            return false;
+        }
+        if child < 0 {
+            return self.leaves.get((-1 - child) as usize).is_some_and(|leaf| {
+                !matches!(leaf.contents, LeafContent::Solid | LeafContent::Sky)
+            });
+        }
+        let Some(node) = self.nodes.get(child as usize) else {
+            return false;
+        };
+        let Some(plane) = self.planes.get(node.plane_index as usize) else {
+            return false;
+        };
+
+        let d1 = plane.normal.dot(start) - plane.dist;
+        let d2 = plane.normal.dot(end) - plane.dist;
+        if d1 >= 0.0 && d2 >= 0.0 {
+            return self.segment_is_clear(node.children[0], depth + 1, start, end);
+        }
+        if d1 < 0.0 && d2 < 0.0 {
+            return self.segment_is_clear(node.children[1], depth + 1, start, end);
+        }
+
+        let middle = start + (end - start) * (d1 / (d1 - d2));
+        let side = (d1 < 0.0) as usize;
+        self.segment_is_clear(node.children[side], depth + 1, start, middle)
+            && self.segment_is_clear(node.children[1 - side], depth + 1, middle, end)
+    }
+}
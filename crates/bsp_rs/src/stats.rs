@@ -0,0 +1,389 @@
+use std::fmt::{self, Write};
+
+use binrw::BinResult;
+
+use crate::{
+    index_disk_size, read_bsp, read_bsp_lumps, Bsp, BspFormat, BspLumps, ClipNode, Face, Leaf,
+    LumpRecord, Model, Node, Plane, TextureInfo, Vec3, LUMP_CLIPNODES, LUMP_EDGES, LUMP_ENTITIES,
+    LUMP_FACES, LUMP_LEAVES, LUMP_LIGHTING, LUMP_MARKSURFACES, LUMP_MODELS, LUMP_NAMES, LUMP_NODES,
+    LUMP_PLANES, LUMP_SURFEDGES, LUMP_TEXINFO, LUMP_TEXTURES, LUMP_VERTICES, LUMP_VISIBILITY,
+    MAX_MAP_CLIPNODES, MAX_MAP_EDGES, MAX_MAP_ENTSTRING, MAX_MAP_FACES, MAX_MAP_LEAFS,
+    MAX_MAP_LIGHTING, MAX_MAP_MARKSURFACES, MAX_MAP_MIPTEX, MAX_MAP_MODELS, MAX_MAP_NODES,
+    MAX_MAP_PLANES, MAX_MAP_SURFEDGES, MAX_MAP_TEXINFO, MAX_MAP_VERTS, MAX_MAP_VISIBILITY,
+    TEX_SPECIAL,
+};
+
+/// Size of the lightmap textures the engine packs face lightmaps into
+pub const ALLOCBLOCK_SIZE: usize = 128;
+/// Number of lightmap textures the engine can allocate
+pub const MAX_ALLOCBLOCKS: usize = 64;
+
+/// Usage of a single lump
+#[derive(Debug, Clone, PartialEq)]
+pub struct LumpUsage {
+    /// `LUMP_*` constant
+    pub lump: usize,
+    /// Number of records, `None` for lumps of variable sized data
+    pub count: Option<usize>,
+    pub max_count: Option<usize>,
+    /// Size in the file in bytes
+    pub size: usize,
+    pub max_size: usize,
+}
+
+impl LumpUsage {
+    /// How full the lump is, from 0 to 1 and beyond when over the limit
+    pub fn fullness(&self) -> f32 {
+        match (self.count, self.max_count) {
+            (Some(count), Some(max_count)) => count as f32 / max_count as f32,
+            _ => self.size as f32 / self.max_size as f32,
+        }
+    }
+}
+
+/// Number of faces using a texture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureUsage {
+    pub name: String,
+    pub faces: usize,
+    /// Whether the pixels are stored in the map rather than in a WAD
+    pub embedded: bool,
+}
+
+/// Lump usage against the stock engine limits, like `bspinfo` and
+/// `hlbsp -chart` print
+#[derive(Debug, Clone, PartialEq)]
+pub struct BspStats {
+    pub format: BspFormat,
+    pub lumps: Vec<LumpUsage>,
+    /// Lightmap textures the engine allocates for the map
+    pub alloc_blocks: usize,
+    pub textures: Vec<TextureUsage>,
+    /// WAD files listed by the world's `wad` key
+    pub wads: Vec<String>,
+}
+
+impl Bsp {
+    /// Computes the statistics of a map, `lumps` giving the sizes in the file
+    pub fn stats(&self, lumps: &BspLumps) -> BspStats {
+        let format = self.format;
+        let size = |lump: usize| lumps.entries[self.variant.lump_index(lump)].length as usize;
+        let counted = |lump: usize, count: usize, max_count: usize, record_size: usize| LumpUsage {
+            lump,
+            count: Some(count),
+            max_count: Some(max_count),
+            size: size(lump),
+            max_size: max_count * record_size,
+        };
+        let variable = |lump: usize, max_size: usize| LumpUsage {
+            lump,
+            count: None,
+            max_count: None,
+            size: size(lump),
+            max_size,
+        };
+
+        let lumps = vec![
+            counted(
+                LUMP_MODELS,
+                self.models.len(),
+                MAX_MAP_MODELS,
+                Model::disk_size(format),
+            ),
+            counted(
+                LUMP_PLANES,
+                self.planes.len(),
+                MAX_MAP_PLANES,
+                Plane::disk_size(format),
+            ),
+            counted(
+                LUMP_VERTICES,
+                self.vertices.len(),
+                MAX_MAP_VERTS,
+                Vec3::disk_size(format),
+            ),
+            counted(
+                LUMP_NODES,
+                self.nodes.len(),
+                MAX_MAP_NODES,
+                Node::disk_size(format),
+            ),
+            counted(
+                LUMP_TEXINFO,
+                self.texture_infos.len(),
+                MAX_MAP_TEXINFO,
+                TextureInfo::disk_size(format),
+            ),
+            counted(
+                LUMP_FACES,
+                self.faces.len(),
+                MAX_MAP_FACES,
+                Face::disk_size(format),
+            ),
+            counted(
+                LUMP_CLIPNODES,
+                self.clip_nodes.len(),
+                MAX_MAP_CLIPNODES,
+                ClipNode::disk_size(format),
+            ),
+            counted(
+                LUMP_LEAVES,
+                self.leaves.len(),
+                MAX_MAP_LEAFS,
+                Leaf::disk_size(format),
+            ),
+            counted(
+                LUMP_MARKSURFACES,
+                self.mark_surfaces.len(),
+                MAX_MAP_MARKSURFACES,
+                index_disk_size(format),
+            ),
+            counted(
+                LUMP_SURFEDGES,
+                self.surf_edges.len(),
+                MAX_MAP_SURFEDGES,
+                i32::disk_size(format),
+            ),
+            counted(
+                LUMP_EDGES,
+                self.edges.len(),
+                MAX_MAP_EDGES,
+                index_disk_size(format) * 2,
+            ),
+            variable(LUMP_TEXTURES, MAX_MAP_MIPTEX),
+            variable(LUMP_LIGHTING, MAX_MAP_LIGHTING),
+            variable(LUMP_VISIBILITY, MAX_MAP_VISIBILITY),
+            variable(LUMP_ENTITIES, MAX_MAP_ENTSTRING),
+        ];
+
+        let mut textures: Vec<_> = self
+            .textures
+            .iter()
+            .map(|texture| TextureUsage {
+                name: texture.name.clone(),
+                faces: 0,
+                embedded: !texture.indices.is_empty(),
+            })
+            .collect();
+        for face in &self.faces {
+            let Some(texture_info) = self.texture_infos.get(face.texture_info as usize) else {
+                continue;
+            };
+            if let Some(texture) = textures.get_mut(texture_info.miptex as usize) {
+                texture.faces += 1;
+            }
+        }
+
+        let wads = self
+            .entities
+            .first()
+            .and_then(|world| world.get("wad"))
+            .map(|wad| {
+                wad.split(';')
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        BspStats {
+            format,
+            lumps,
+            alloc_blocks: self.alloc_blocks(),
+            textures,
+            wads,
+        }
+    }
+
+    /// Packs the face lightmaps like the engine's `GL_BuildLightmaps`,
+    /// returning the number of lightmap textures used
+    ///
+    /// Unlike the engine, which fails past [MAX_ALLOCBLOCKS], textures keep
+    /// being added so the count shows how far over the limit a map is.
+    fn alloc_blocks(&self) -> usize {
+        let mut blocks: Vec<[usize; ALLOCBLOCK_SIZE]> = Vec::new();
+
+        for face in &self.faces {
+            let special = self
+                .texture_infos
+                .get(face.texture_info as usize)
+                .is_none_or(|texture_info| texture_info.flags & TEX_SPECIAL != 0);
+            if special || face.edges < 3 {
+                continue;
+            }
+
+            // Lightmaps as wide as a texture never fit, the engine errors out
+            let (width, height) = self.lightmap_size(face);
+            let width = (width as usize).min(ALLOCBLOCK_SIZE - 1);
+            let height = (height as usize).min(ALLOCBLOCK_SIZE);
+
+            // Every texture is tried again for each face, earlier ones first
+            if !blocks
+                .iter_mut()
+                .any(|allocated| alloc_block(allocated, width, height))
+            {
+                let mut allocated = [0; ALLOCBLOCK_SIZE];
+                alloc_block(&mut allocated, width, height);
+                blocks.push(allocated);
+            }
+        }
+
+        blocks.len()
+    }
+}
+
+/// Takes the lowest spot of a lightmap texture a lightmap fits in, like a
+/// single texture iteration of the engine's `AllocBlock`
+fn alloc_block(allocated: &mut [usize; ALLOCBLOCK_SIZE], width: usize, height: usize) -> bool {
+    let mut best = ALLOCBLOCK_SIZE;
+    let mut x = 0;
+    // The engine never tries the rightmost spot
+    for i in 0..ALLOCBLOCK_SIZE - width {
+        let top = allocated[i..i + width].iter().copied().max().unwrap_or(0);
+        if top < best {
+            best = top;
+            x = i;
+        }
+    }
+
+    if best + height > ALLOCBLOCK_SIZE {
+        return false;
+    }
+    allocated[x..x + width].fill(best + height);
+    true
+}
+
+/// Reads a map and computes its statistics, see [Bsp::stats]
+pub fn read_bsp_stats(bytes: &[u8]) -> BinResult<BspStats> {
+    let bsp = read_bsp(bytes)?;
+    let lumps = read_bsp_lumps(bytes)?;
+    Ok(bsp.stats(&lumps))
+}
+
+impl BspStats {
+    /// Writes the statistics as a JSON object
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        let optional = |value: Option<usize>| value.map_or("null".to_string(), |v| v.to_string());
+
+        write!(json, "{{\"format\":\"{:?}\",\"lumps\":[", self.format).unwrap();
+        for (i, lump) in self.lumps.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"name\":\"{}\",\"count\":{},\"max_count\":{},\"size\":{},\"max_size\":{}}}",
+                LUMP_NAMES[lump.lump],
+                optional(lump.count),
+                optional(lump.max_count),
+                lump.size,
+                lump.max_size
+            )
+            .unwrap();
+        }
+
+        write!(
+            json,
+            "],\"alloc_blocks\":{},\"max_alloc_blocks\":{},\"textures\":[",
+            self.alloc_blocks, MAX_ALLOCBLOCKS
+        )
+        .unwrap();
+        for (i, texture) in self.textures.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"name\":{},\"faces\":{},\"embedded\":{}}}",
+                json_string(&texture.name),
+                texture.faces,
+                texture.embedded
+            )
+            .unwrap();
+        }
+
+        json.push_str("],\"wads\":[");
+        for (i, wad) in self.wads.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&json_string(wad));
+        }
+        json.push_str("]}");
+
+        json
+    }
+}
+
+impl fmt::Display for BspStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Object names  Objects/Maxobjs  Memory / Maxmem  Fullness"
+        )?;
+        writeln!(
+            f,
+            "------------  ---------------  ---------------  --------"
+        )?;
+        for lump in &self.lumps {
+            let objects = match (lump.count, lump.max_count) {
+                (Some(count), Some(max_count)) => format!("{:>7}/{:<7}", count, max_count),
+                _ => format!("{:^15}", "[variable]"),
+            };
+            writeln!(
+                f,
+                "{:<12}  {}  {:>7}/{:<7}  ({:5.1}%)",
+                LUMP_NAMES[lump.lump],
+                objects,
+                lump.size,
+                lump.max_size,
+                lump.fullness() * 100.0
+            )?;
+        }
+
+        let total: usize = self.lumps.iter().map(|lump| lump.size).sum();
+        writeln!(f, "=== Total BSP file data space used: {} bytes ===", total)?;
+        writeln!(
+            f,
+            "{:<12}  {:>7}/{:<7}                   ({:5.1}%)",
+            "allocblock",
+            self.alloc_blocks,
+            MAX_ALLOCBLOCKS,
+            self.alloc_blocks as f32 / MAX_ALLOCBLOCKS as f32 * 100.0
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "Textures  Faces  Source")?;
+        for texture in &self.textures {
+            let source = if texture.embedded { "bsp" } else { "wad" };
+            writeln!(f, "{:<16}  {:>5}  {}", texture.name, texture.faces, source)?;
+        }
+
+        if !self.wads.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Wads")?;
+            for wad in &self.wads {
+                writeln!(f, "{}", wad)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Quotes and escapes a JSON string
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
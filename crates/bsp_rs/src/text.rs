@@ -0,0 +1,96 @@
+use std::{error::Error, fmt, iter::Peekable, str::CharIndices};
+
+/// Syntax error in a text file, like an FGD or a map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextParseError {
+    /// Line of the offending token, starting at 1
+    pub line: usize,
+    /// Column of the offending token in characters, starting at 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TextParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for TextParseError {}
+
+/// Reads text a character at a time, keeping track of the line and column
+/// for error messages
+pub struct TextCursor<'a> {
+    data: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    /// Line of the next character, starting at 1
+    pub line: usize,
+    /// Column of the next character in characters, starting at 1
+    pub column: usize,
+}
+
+impl<'a> TextCursor<'a> {
+    pub fn new(data: &'a str) -> Self {
+        Self {
+            data,
+            chars: data.char_indices().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<(usize, char)> {
+        self.chars.peek().copied()
+    }
+
+    pub fn bump(&mut self) -> Option<(usize, char)> {
+        let (i, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some((i, c))
+    }
+
+    /// Skips whitespace, `//` comments and characters matching `skip`
+    pub fn skip_blank(&mut self, skip: impl Fn(char) -> bool) {
+        while let Some((i, c)) = self.peek() {
+            if c.is_whitespace() || skip(c) {
+                self.bump();
+            } else if self.data[i..].starts_with("//") {
+                while self.peek().is_some_and(|(_, c)| c != '\n') {
+                    self.bump();
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Reads the rest of a string whose opening quote at `start` was just
+    /// read, `None` when the closing quote is missing
+    pub fn quoted(&mut self, start: usize) -> Option<&'a str> {
+        loop {
+            match self.bump()? {
+                (end, '"') => return Some(&self.data[start + 1..end]),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Reads the rest of a word whose first character at `start` was just
+    /// read, up to whitespace or a character matching `stop`
+    pub fn word(&mut self, start: usize, stop: impl Fn(char) -> bool) -> &'a str {
+        let mut end = self.data.len();
+        while let Some((i, c)) = self.peek() {
+            if c.is_whitespace() || stop(c) {
+                end = i;
+                break;
+            }
+            self.bump();
+        }
+        &self.data[start..end]
+    }
+}
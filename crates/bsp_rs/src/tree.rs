@@ -0,0 +1,236 @@
+use crate::{BoundBox, Bsp, Leaf, Node, Plane, Vec3};
+
+/// Volumes of the looping sounds `vis` assigns to a leaf, from 0 to 1
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AmbientLevels {
+    pub water: f32,
+    pub sky: f32,
+    pub slime: f32,
+    pub lava: f32,
+}
+
+impl From<[u8; 4]> for AmbientLevels {
+    fn from(levels: [u8; 4]) -> Self {
+        let [water, sky, slime, lava] = levels.map(|level| level as f32 / 255.0);
+        Self {
+            water,
+            sky,
+            slime,
+            lava,
+        }
+    }
+}
+
+impl Leaf {
+    pub fn bounds(&self) -> BoundBox {
+        bounds(self.mins, self.maxs)
+    }
+
+    pub fn ambient(&self) -> AmbientLevels {
+        self.ambient_levels.into()
+    }
+}
+
+impl Node {
+    pub fn bounds(&self) -> BoundBox {
+        bounds(self.mins, self.maxs)
+    }
+}
+
+impl Bsp {
+    /// Index of the world leaf containing a point, like the engine's
+    /// `Mod_PointInLeaf`
+    ///
+    /// Points outside the world end up in leaf 0, the shared solid leaf.
+    pub fn leaf_at(&self, point: Vec3) -> usize {
+        let mut child = self.models.first().map_or(0, |world| world.head_nodes[0]);
+
+        // A path visits each node at most once, unless a corrupt tree loops
+        for _ in 0..self.nodes.len() {
+            if child < 0 {
+                break;
+            }
+            let Some(node) = self.nodes.get(child as usize) else {
+                return 0;
+            };
+            let Some(plane) = self.planes.get(node.plane_index as usize) else {
+                return 0;
+            };
+            let side = plane.normal.dot(point) - plane.dist <= 0.0;
+            child = node.children[side as usize];
+        }
+
+        if child < 0 {
+            (-1 - child) as usize
+        } else {
+            0
+        }
+    }
+
+    /// Ambient sound volumes heard at a point, before the engine's
+    /// `ambient_level` scale and fading
+    pub fn ambient_at(&self, point: Vec3) -> AmbientLevels {
+        self.leaves
+            .get(self.leaf_at(point))
+            .map(Leaf::ambient)
+            .unwrap_or_default()
+    }
+
+    /// Indices of the world leaves touching a box, the shared solid leaf 0
+    /// left out
+    pub fn leaves_in_box(&self, bounds: &BoundBox) -> Vec<usize> {
+        let mut leaves = Vec::new();
+        self.walk_box(bounds, &mut |child| {
+            let leaf = (-1 - child) as usize;
+            if leaf != 0 && self.leaves.get(leaf).is_some() {
+                leaves.push(leaf);
+            }
+        });
+        leaves
+    }
+
+    /// Indices of the world faces touching a box
+    ///
+    /// Brush entities have their faces in their own tree, use
+    /// [BrushEntity::bounds](crate::BrushEntity::bounds) to find them.
+    pub fn faces_in_box(&self, bounds: &BoundBox) -> Vec<usize> {
+        let mut faces = Vec::new();
+        self.walk_box_nodes(bounds, &mut |node| {
+            let first = node.first_face as usize;
+            for index in first..first + node.num_faces as usize {
+                let Some(face) = self.faces.get(index) else {
+                    break;
+                };
+                let mut vertices = self.face_vertices(face);
+                let Some(first) = vertices.next() else {
+                    continue;
+                };
+                let face_bounds = vertices.fold(
+                    BoundBox {
+                        min: first,
+                        max: first,
+                    },
+                    |b, v| BoundBox {
+                        min: Vec3::new(b.min.x.min(v.x), b.min.y.min(v.y), b.min.z.min(v.z)),
+                        max: Vec3::new(b.max.x.max(v.x), b.max.y.max(v.y), b.max.z.max(v.z)),
+                    },
+                );
+                if intersects(bounds, &face_bounds) {
+                    faces.push(index);
+                }
+            }
+        });
+        faces
+    }
+
+    /// Indices of the entities with an origin, or a brush model, within a
+    /// radius of a point
+    ///
+    /// Brush entities are measured from the closest point of their bounds,
+    /// `worldspawn` is left out.
+    pub fn entities_in_radius(&self, center: Vec3, radius: f32) -> Vec<usize> {
+        let brush_entities = self.brush_entities();
+        let mut entities = Vec::new();
+
+        for (index, entity) in self.entities.iter().enumerate().skip(1) {
+            let closest = match brush_entities
+                .iter()
+                .find(|brush_entity| brush_entity.entity_index == index)
+            {
+                Some(brush_entity) => {
+                    let BoundBox { min, max } = brush_entity.bounds();
+                    Vec3::new(
+                        center.x.clamp(min.x, max.x),
+                        center.y.clamp(min.y, max.y),
+                        center.z.clamp(min.z, max.z),
+                    )
+                }
+                None => {
+                    let Some(origin) = entity.get_vec3("origin") else {
+                        continue;
+                    };
+                    origin
+                }
+            };
+            if (closest - center).length() <= radius {
+                entities.push(index);
+            }
+        }
+
+        entities
+    }
+
+    /// Calls `leaf` with the child index of every leaf touching a box
+    fn walk_box(&self, bounds: &BoundBox, leaf: &mut impl FnMut(i32)) {
+        let root = self.models.first().map_or(0, |world| world.head_nodes[0]);
+        self.walk_box_from(root, bounds, leaf, &mut |_| {});
+    }
+
+    /// Calls `visit` with every node whose plane crosses a box
+    fn walk_box_nodes(&self, bounds: &BoundBox, visit: &mut impl FnMut(&Node)) {
+        let root = self.models.first().map_or(0, |world| world.head_nodes[0]);
+        self.walk_box_from(root, bounds, &mut |_| {}, visit);
+    }
+
+    fn walk_box_from(
+        &self,
+        child: i32,
+        bounds: &BoundBox,
+        leaf: &mut impl FnMut(i32),
+        visit: &mut impl FnMut(&Node),
+    ) {
+        if child < 0 {
+            leaf(child);
+            return;
+        }
+        let Some(node) = self.nodes.get(child as usize) else {
+            return;
+        };
+        let Some(plane) = self.planes.get(node.plane_index as usize) else {
+            return;
+        };
+        if !intersects(bounds, &node.bounds()) {
+            return;
+        }
+
+        let (front, back) = box_sides(bounds, plane);
+        if front && back {
+            visit(node);
+        }
+        if front {
+            self.walk_box_from(node.children[0], bounds, leaf, visit);
+        }
+        if back {
+            self.walk_box_from(node.children[1], bounds, leaf, visit);
+        }
+    }
+}
+
+fn bounds(mins: [f32; 3], maxs: [f32; 3]) -> BoundBox {
+    BoundBox {
+        min: Vec3::new(mins[0], mins[1], mins[2]),
+        max: Vec3::new(maxs[0], maxs[1], maxs[2]),
+    }
+}
+
+fn intersects(a: &BoundBox, b: &BoundBox) -> bool {
+    a.min.x <= b.max.x
+        && a.max.x >= b.min.x
+        && a.min.y <= b.max.y
+        && a.max.y >= b.min.y
+        && a.min.z <= b.max.z
+        && a.max.z >= b.min.z
+}
+
+/// Whether a box reaches in front of and behind a plane, boxes touching the
+/// plane reaching both sides like they touch boxes in [intersects]
+fn box_sides(bounds: &BoundBox, plane: &Plane) -> (bool, bool) {
+    let n = plane.normal;
+    let pick = |axis: f32, min: f32, max: f32| if axis >= 0.0 { (min, max) } else { (max, min) };
+    let (near_x, far_x) = pick(n.x, bounds.min.x, bounds.max.x);
+    let (near_y, far_y) = pick(n.y, bounds.min.y, bounds.max.y);
+    let (near_z, far_z) = pick(n.z, bounds.min.z, bounds.max.z);
+    let near = n.dot(Vec3::new(near_x, near_y, near_z)) - plane.dist;
+    let far = n.dot(Vec3::new(far_x, far_y, far_z)) - plane.dist;
+    (far >= 0.0, near <= 0.0)
+}
@@ -0,0 +1,481 @@
+use std::fmt;
+
+use crate::{
+    write_entities, Bsp, Face, LUMP_CLIPNODES, LUMP_EDGES, LUMP_ENTITIES, LUMP_FACES, LUMP_LEAVES,
+    LUMP_LIGHTING, LUMP_MARKSURFACES, LUMP_MODELS, LUMP_NAMES, LUMP_NODES, LUMP_PLANES,
+    LUMP_SURFEDGES, LUMP_TEXINFO, LUMP_TEXTURES, LUMP_VERTICES, LUMP_VISIBILITY, STYLE_NONE,
+};
+
+pub const MAX_MAP_MODELS: usize = 400;
+pub const MAX_MAP_ENTITIES: usize = 1024;
+pub const MAX_MAP_PLANES: usize = 32767;
+pub const MAX_MAP_NODES: usize = 32767;
+pub const MAX_MAP_CLIPNODES: usize = 32767;
+pub const MAX_MAP_LEAFS: usize = 8192;
+pub const MAX_MAP_VERTS: usize = 65535;
+pub const MAX_MAP_FACES: usize = 65535;
+pub const MAX_MAP_MARKSURFACES: usize = 65535;
+pub const MAX_MAP_TEXINFO: usize = 8192;
+pub const MAX_MAP_EDGES: usize = 256000;
+pub const MAX_MAP_SURFEDGES: usize = 512000;
+pub const MAX_MAP_TEXTURES: usize = 512;
+/// Size of the texture lump in bytes
+pub const MAX_MAP_MIPTEX: usize = 0x200000;
+/// Size of the lighting lump in bytes
+pub const MAX_MAP_LIGHTING: usize = 0x200000;
+/// Size of the visibility lump in bytes
+pub const MAX_MAP_VISIBILITY: usize = 0x200000;
+/// Size of the entity lump in bytes
+pub const MAX_MAP_ENTSTRING: usize = 128 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Likely to look wrong, or to fail on engines with stock limits
+    Warning,
+    /// Likely to crash the engine
+    Error,
+}
+
+/// A problem found by [Bsp::validate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BspIssue {
+    pub severity: Severity,
+    pub kind: BspIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BspIssueKind {
+    /// A record references a missing record of another lump
+    InvalidIndex {
+        /// `LUMP_*` constant of the referencing record
+        lump: usize,
+        record: usize,
+        /// `LUMP_*` constant of the referenced record
+        target: usize,
+        index: i64,
+    },
+    /// A record references a range running past the end of another lump
+    InvalidRange {
+        lump: usize,
+        record: usize,
+        target: usize,
+        first: i64,
+        count: i64,
+    },
+    /// A face's lightmap runs past the end of the lighting lump
+    LightmapOutOfBounds { face: usize, offset: u32, size: u32 },
+    /// A face with less than 3 edges
+    DegenerateFace(usize),
+    /// A lump holds more records than the engine allows
+    LimitExceeded {
+        lump: usize,
+        count: usize,
+        limit: usize,
+    },
+    /// A lump is larger in bytes than the engine allows
+    SizeExceeded {
+        lump: usize,
+        size: usize,
+        limit: usize,
+    },
+}
+
+impl fmt::Display for BspIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: ")?,
+            Severity::Error => write!(f, "error: ")?,
+        }
+        match &self.kind {
+            BspIssueKind::InvalidIndex {
+                lump,
+                record,
+                target,
+                index,
+            } => write!(
+                f,
+                "{} {} references missing {} {}",
+                LUMP_NAMES[*lump], record, LUMP_NAMES[*target], index
+            ),
+            BspIssueKind::InvalidRange {
+                lump,
+                record,
+                target,
+                first,
+                count,
+            } => write!(
+                f,
+                "{} {} references {} {}..{} out of bounds",
+                LUMP_NAMES[*lump],
+                record,
+                LUMP_NAMES[*target],
+                first,
+                first + count
+            ),
+            BspIssueKind::LightmapOutOfBounds { face, offset, size } => write!(
+                f,
+                "lightmap of face {} at {} ({} bytes) is out of bounds",
+                face, offset, size
+            ),
+            BspIssueKind::DegenerateFace(face) => write!(f, "face {} has less than 3 edges", face),
+            BspIssueKind::LimitExceeded { lump, count, limit } => write!(
+                f,
+                "{} {} exceeds the limit of {}",
+                count, LUMP_NAMES[*lump], limit
+            ),
+            BspIssueKind::SizeExceeded { lump, size, limit } => write!(
+                f,
+                "{} lump of {} bytes exceeds the limit of {}",
+                LUMP_NAMES[*lump], size, limit
+            ),
+        }
+    }
+}
+
+/// Collects issues while walking the lumps
+#[derive(Default)]
+struct Issues(Vec<BspIssue>);
+
+impl Issues {
+    fn push(&mut self, severity: Severity, kind: BspIssueKind) {
+        self.0.push(BspIssue { severity, kind });
+    }
+
+    /// Checks `index < len`, returning whether it is
+    fn index(&mut self, lump: usize, record: usize, target: usize, index: i64, len: usize) -> bool {
+        let valid = index >= 0 && (index as u64) < len as u64;
+        if !valid {
+            self.push(
+                Severity::Error,
+                BspIssueKind::InvalidIndex {
+                    lump,
+                    record,
+                    target,
+                    index,
+                },
+            );
+        }
+        valid
+    }
+
+    /// Checks `first..first + count` lies within `0..len`, returning whether it does
+    fn range(
+        &mut self,
+        lump: usize,
+        record: usize,
+        target: usize,
+        first: i64,
+        count: i64,
+        len: usize,
+    ) -> bool {
+        let valid = first >= 0 && count >= 0 && (first + count) as u64 <= len as u64;
+        if !valid {
+            self.push(
+                Severity::Error,
+                BspIssueKind::InvalidRange {
+                    lump,
+                    record,
+                    target,
+                    first,
+                    count,
+                },
+            );
+        }
+        valid
+    }
+
+    fn limit(&mut self, lump: usize, count: usize, limit: usize) {
+        if count > limit {
+            self.push(
+                Severity::Warning,
+                BspIssueKind::LimitExceeded { lump, count, limit },
+            );
+        }
+    }
+
+    fn size(&mut self, lump: usize, size: usize, limit: usize) {
+        if size > limit {
+            self.push(
+                Severity::Warning,
+                BspIssueKind::SizeExceeded { lump, size, limit },
+            );
+        }
+    }
+}
+
+impl Bsp {
+    /// Checks every cross-reference between lumps and the engine limits,
+    /// errors first
+    ///
+    /// Limits are only checked for formats with 16-bit indices, BSP2 exists
+    /// to lift them.
+    pub fn validate(&self) -> Vec<BspIssue> {
+        let mut issues = Issues::default();
+
+        if !self.format.has_wide_indices() {
+            for (lump, count, limit) in [
+                (LUMP_ENTITIES, self.entities.len(), MAX_MAP_ENTITIES),
+                (LUMP_PLANES, self.planes.len(), MAX_MAP_PLANES),
+                (LUMP_TEXTURES, self.textures.len(), MAX_MAP_TEXTURES),
+                (LUMP_VERTICES, self.vertices.len(), MAX_MAP_VERTS),
+                (LUMP_NODES, self.nodes.len(), MAX_MAP_NODES),
+                (LUMP_TEXINFO, self.texture_infos.len(), MAX_MAP_TEXINFO),
+                (LUMP_FACES, self.faces.len(), MAX_MAP_FACES),
+                (LUMP_CLIPNODES, self.clip_nodes.len(), MAX_MAP_CLIPNODES),
+                (LUMP_LEAVES, self.leaves.len(), MAX_MAP_LEAFS),
+                (
+                    LUMP_MARKSURFACES,
+                    self.mark_surfaces.len(),
+                    MAX_MAP_MARKSURFACES,
+                ),
+                (LUMP_EDGES, self.edges.len(), MAX_MAP_EDGES),
+                (LUMP_SURFEDGES, self.surf_edges.len(), MAX_MAP_SURFEDGES),
+                (LUMP_MODELS, self.models.len(), MAX_MAP_MODELS),
+            ] {
+                issues.limit(lump, count, limit);
+            }
+
+            for (lump, size, limit) in [
+                (LUMP_ENTITIES, self.entity_lump_size(), MAX_MAP_ENTSTRING),
+                (LUMP_TEXTURES, self.texture_lump_size(), MAX_MAP_MIPTEX),
+                (LUMP_VISIBILITY, self.vis_size as usize, MAX_MAP_VISIBILITY),
+                (LUMP_LIGHTING, self.lighting_lump_size(), MAX_MAP_LIGHTING),
+            ] {
+                issues.size(lump, size, limit);
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let lump = LUMP_NODES;
+            issues.index(
+                lump,
+                i,
+                LUMP_PLANES,
+                node.plane_index as i64,
+                self.planes.len(),
+            );
+            for child in node.children {
+                if child >= 0 {
+                    issues.index(lump, i, LUMP_NODES, child as i64, self.nodes.len());
+                } else {
+                    issues.index(lump, i, LUMP_LEAVES, -1 - child as i64, self.leaves.len());
+                }
+            }
+            issues.range(
+                lump,
+                i,
+                LUMP_FACES,
+                node.first_face as i64,
+                node.num_faces as i64,
+                self.faces.len(),
+            );
+        }
+
+        for (i, clip_node) in self.clip_nodes.iter().enumerate() {
+            let lump = LUMP_CLIPNODES;
+            issues.index(
+                lump,
+                i,
+                LUMP_PLANES,
+                clip_node.plane_index as i64,
+                self.planes.len(),
+            );
+            // Negative children are contents
+            for child in clip_node.children.into_iter().filter(|&c| c >= 0) {
+                issues.index(lump, i, LUMP_CLIPNODES, child as i64, self.clip_nodes.len());
+            }
+        }
+
+        for (i, texture_info) in self.texture_infos.iter().enumerate() {
+            issues.index(
+                LUMP_TEXINFO,
+                i,
+                LUMP_TEXTURES,
+                texture_info.miptex as i64,
+                self.textures.len(),
+            );
+        }
+
+        let mut valid_edges = true;
+        for (i, edge) in self.edges.iter().enumerate() {
+            for vertex in [edge.start, edge.end] {
+                valid_edges &= issues.index(
+                    LUMP_EDGES,
+                    i,
+                    LUMP_VERTICES,
+                    vertex as i64,
+                    self.vertices.len(),
+                );
+            }
+        }
+
+        let mut valid_surf_edges = true;
+        for (i, &surf_edge) in self.surf_edges.iter().enumerate() {
+            valid_surf_edges &= issues.index(
+                LUMP_SURFEDGES,
+                i,
+                LUMP_EDGES,
+                surf_edge.unsigned_abs() as i64,
+                self.edges.len(),
+            );
+        }
+
+        for (i, face) in self.faces.iter().enumerate() {
+            let lump = LUMP_FACES;
+            issues.index(
+                lump,
+                i,
+                LUMP_PLANES,
+                face.plane_index as i64,
+                self.planes.len(),
+            );
+            let valid_range = issues.range(
+                lump,
+                i,
+                LUMP_SURFEDGES,
+                face.first_edge as i64,
+                face.edges as i64,
+                self.surf_edges.len(),
+            );
+            let valid_texture_info = issues.index(
+                lump,
+                i,
+                LUMP_TEXINFO,
+                face.texture_info as i64,
+                self.texture_infos.len(),
+            );
+            if face.edges < 3 {
+                issues.push(Severity::Warning, BspIssueKind::DegenerateFace(i));
+            }
+
+            // The lightmap size needs the face's vertices
+            if valid_range && valid_texture_info && valid_surf_edges && valid_edges {
+                self.validate_lightmap(&mut issues, i, face);
+            }
+        }
+
+        for (i, leaf) in self.leaves.iter().enumerate() {
+            issues.range(
+                LUMP_LEAVES,
+                i,
+                LUMP_MARKSURFACES,
+                leaf.first_mark_surface as i64,
+                leaf.mark_surface as i64,
+                self.mark_surfaces.len(),
+            );
+            if leaf.vis_offset >= 0 {
+                issues.index(
+                    LUMP_LEAVES,
+                    i,
+                    LUMP_VISIBILITY,
+                    leaf.vis_offset as i64,
+                    self.vis_size as usize,
+                );
+            }
+        }
+
+        for (i, &face) in self.mark_surfaces.iter().enumerate() {
+            issues.index(
+                LUMP_MARKSURFACES,
+                i,
+                LUMP_FACES,
+                face as i64,
+                self.faces.len(),
+            );
+        }
+
+        for (i, model) in self.models.iter().enumerate() {
+            let lump = LUMP_MODELS;
+            issues.index(
+                lump,
+                i,
+                LUMP_NODES,
+                model.head_nodes[0] as i64,
+                self.nodes.len(),
+            );
+            // Hulls without clip nodes have a negative head node
+            for &head_node in model.head_nodes[1..].iter().filter(|&&n| n >= 0) {
+                issues.index(
+                    lump,
+                    i,
+                    LUMP_CLIPNODES,
+                    head_node as i64,
+                    self.clip_nodes.len(),
+                );
+            }
+            issues.range(
+                lump,
+                i,
+                LUMP_FACES,
+                model.first_face as i64,
+                model.faces as i64,
+                self.faces.len(),
+            );
+            // Leaf 0 is the shared solid leaf, the visible ones follow it
+            issues.range(
+                lump,
+                i,
+                LUMP_LEAVES,
+                1,
+                model.vis_leafs as i64,
+                self.leaves.len(),
+            );
+        }
+
+        let mut issues = issues.0;
+        issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+        issues
+    }
+
+    /// Size of the entity lump as [write_entities] would write it
+    fn entity_lump_size(&self) -> usize {
+        write_entities(&self.entities).map_or(0, |data| data.len() + 1)
+    }
+
+    /// Size of the texture lump, textures missing from it taking no space
+    fn texture_lump_size(&self) -> usize {
+        let textures = self
+            .textures
+            .iter()
+            .filter(|t| !t.name.is_empty() || t.width != 0);
+        let data: usize = textures
+            .map(|texture| {
+                let mut size = 40 + texture.indices.iter().map(Vec::len).sum::<usize>();
+                if !texture.palette.is_empty() {
+                    size += 2 + texture.palette.len() * 3;
+                }
+                (size + 3) & !3
+            })
+            .sum();
+        4 + self.textures.len() * 4 + data
+    }
+
+    /// Size of the lighting lump, Quake maps store a single channel
+    fn lighting_lump_size(&self) -> usize {
+        if self.format.is_goldsrc() {
+            self.lightmap.len()
+        } else {
+            self.lightmap.len() / 3
+        }
+    }
+
+    fn validate_lightmap(&self, issues: &mut Issues, index: usize, face: &Face) {
+        if face.lightmap_offset == u32::MAX || face.styles[0] == STYLE_NONE {
+            return;
+        }
+
+        let (width, height) = self.lightmap_size(face);
+        let styles = face.styles.iter().take_while(|&&s| s != STYLE_NONE).count() as u64;
+        let size = width as u64 * height as u64 * 3 * styles;
+        if face.lightmap_offset as u64 + size > self.lightmap.len() as u64 {
+            issues.push(
+                Severity::Error,
+                BspIssueKind::LightmapOutOfBounds {
+                    face: index,
+                    offset: face.lightmap_offset,
+                    size: size as u32,
+                },
+            );
+        }
+    }
+}
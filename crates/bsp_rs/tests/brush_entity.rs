@@ -0,0 +1,102 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+const ENTITIES: &str = r#"{ "classname" "worldspawn" }
+{ "classname" "func_rotating" "model" "*0" "origin" "100 0 0" "angles" "0 90 0" "rendermode" "2" "renderamt" "300" "rendercolor" "255 128 0" "renderfx" "16" }
+{ "classname" "func_door_rotating" "model" "*0" "angles" "90 0 0" "rendermode" "9" }
+{ "classname" "func_wall" "model" "*5" }
+"#;
+
+fn bsp() -> Bsp {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_ENTITIES] = ENTITIES.bytes().chain([0]).collect();
+    read_bsp(&builder.build()).unwrap()
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn pairs_entities_with_models() {
+    let bsp = bsp();
+    let brush_entities = bsp.brush_entities();
+
+    let indices: Vec<_> = brush_entities
+        .iter()
+        .map(|b| (b.entity_index, b.model_index))
+        .collect();
+    assert_eq!(indices, [(0, 0), (1, 0), (2, 0)]);
+
+    let world = &brush_entities[0];
+    assert_eq!(world.render_mode, RenderMode::Normal);
+    assert_eq!(
+        world.transform_point(Vec3::new(1.0, 2.0, 3.0)),
+        Vec3::new(1.0, 2.0, 3.0)
+    );
+}
+
+#[test]
+fn decodes_render_settings() {
+    let bsp = bsp();
+    let brush_entities = bsp.brush_entities();
+
+    let rotating = &brush_entities[1];
+    assert_eq!(rotating.render_mode, RenderMode::Texture);
+    // Clamped to a byte
+    assert_eq!(rotating.render_amount, 255);
+    assert_eq!(
+        rotating.render_color,
+        Rgb {
+            r: 255,
+            g: 128,
+            b: 0
+        }
+    );
+    assert_eq!(rotating.render_fx, RenderFx::Hologram);
+
+    // Unknown modes fall back to normal
+    let door = &brush_entities[2];
+    assert_eq!(door.render_mode, RenderMode::Normal);
+    assert_eq!(door.render_amount, 0);
+    assert_eq!(door.render_fx, RenderFx::None);
+}
+
+#[test]
+fn transforms_yawed_models() {
+    let bsp = bsp();
+    let rotating = &bsp.brush_entities()[1];
+
+    // Turned left around the origin, then moved to it
+    assert_close(
+        rotating.transform_point(Vec3::new(16.0, 0.0, 0.0)),
+        Vec3::new(100.0, 16.0, 0.0),
+    );
+
+    let bounds = rotating.bounds();
+    assert_close(bounds.min, Vec3::new(84.0, 0.0, -8.0));
+    assert_close(bounds.max, Vec3::new(100.0, 16.0, 8.0));
+}
+
+#[test]
+fn transforms_pitched_models() {
+    let bsp = bsp();
+    let door = &bsp.brush_entities()[2];
+
+    // The engine negates the pitch of brush models, tilting +X up
+    assert_close(
+        door.transform_point(Vec3::new(16.0, 0.0, 0.0)),
+        Vec3::new(0.0, 0.0, 16.0),
+    );
+    assert_close(
+        door.transform_point(Vec3::new(0.0, 0.0, 8.0)),
+        Vec3::new(-8.0, 0.0, 0.0),
+    );
+
+    let bounds = door.bounds();
+    assert_close(bounds.min, Vec3::new(-8.0, 0.0, 0.0));
+    assert_close(bounds.max, Vec3::new(8.0, 16.0, 16.0));
+}
@@ -0,0 +1,187 @@
+#![allow(dead_code)]
+
+use bsp_rs::*;
+
+/// Writes the on-disk layout of a tiny map: a single 16x16 square face
+pub struct Builder {
+    pub format: BspFormat,
+    pub lumps: [Vec<u8>; HEADER_LUMPS],
+}
+
+impl Builder {
+    pub fn new(format: BspFormat) -> Self {
+        let mut builder = Self {
+            format,
+            lumps: Default::default(),
+        };
+
+        builder.lumps[LUMP_ENTITIES] = b"{\n\"classname\" \"worldspawn\"\n}\n\0".to_vec();
+
+        for (normal, dist, kind) in [([0.0, 0.0, 1.0], 0.0, 2u32), ([1.0, 0.0, 0.0], 8.0, 0)] {
+            let lump = &mut builder.lumps[LUMP_PLANES];
+            floats(lump, &normal);
+            floats(lump, &[dist]);
+            lump.extend(kind.to_le_bytes());
+        }
+
+        builder.lumps[LUMP_TEXTURES] = builder.texture();
+
+        for vertex in [
+            [0.0, 0.0, 0.0],
+            [16.0, 0.0, 0.0],
+            [16.0, 16.0, 0.0],
+            [0.0, 16.0, 0.0],
+        ] {
+            floats(&mut builder.lumps[LUMP_VERTICES], &vertex);
+        }
+
+        builder.lumps[LUMP_VISIBILITY] = vec![0xff; 3];
+
+        // Node: plane, children, mins, maxs, first face, face count
+        let lump = &mut builder.lumps[LUMP_NODES];
+        lump.extend(0u32.to_le_bytes());
+        builder.signed(LUMP_NODES, -2);
+        builder.signed(LUMP_NODES, -1);
+        builder.bounds(LUMP_NODES, [0, 0, -8]);
+        builder.bounds(LUMP_NODES, [16, 16, 8]);
+        builder.index(LUMP_NODES, 0);
+        builder.index(LUMP_NODES, 1);
+
+        let lump = &mut builder.lumps[LUMP_TEXINFO];
+        floats(lump, &[1.0, 0.0, 0.0, 16.0, 0.0, 1.0, 0.0, 32.0]);
+        lump.extend(0u32.to_le_bytes());
+        lump.extend(0u32.to_le_bytes());
+
+        // Face: plane, side, first edge, edge count, texinfo, styles, lightmap
+        builder.index(LUMP_FACES, 0);
+        builder.index(LUMP_FACES, 1);
+        builder.lumps[LUMP_FACES].extend(0u32.to_le_bytes());
+        builder.index(LUMP_FACES, 4);
+        builder.index(LUMP_FACES, 0);
+        builder.lumps[LUMP_FACES].extend([0, 255, 255, 255]);
+        builder.lumps[LUMP_FACES].extend(0u32.to_le_bytes());
+
+        // 2x2 samples
+        builder.lumps[LUMP_LIGHTING] = if format.is_goldsrc() {
+            (0..12).collect()
+        } else {
+            (0..4).collect()
+        };
+
+        builder.lumps[LUMP_CLIPNODES].extend(1u32.to_le_bytes());
+        builder.signed(LUMP_CLIPNODES, -1);
+        builder.signed(LUMP_CLIPNODES, -2);
+
+        for (contents, first) in [(-2i32, 0), (-1, 0)] {
+            builder.lumps[LUMP_LEAVES].extend(contents.to_le_bytes());
+            builder.lumps[LUMP_LEAVES].extend((-1i32).to_le_bytes());
+            builder.bounds(LUMP_LEAVES, [0, 0, -8]);
+            builder.bounds(LUMP_LEAVES, [16, 16, 8]);
+            builder.index(LUMP_LEAVES, first);
+            builder.index(LUMP_LEAVES, 1);
+            builder.lumps[LUMP_LEAVES].extend([1, 2, 3, 4]);
+        }
+
+        builder.index(LUMP_MARKSURFACES, 0);
+
+        for (start, end) in [(0, 0), (0, 1), (1, 2), (2, 3), (0, 3)] {
+            builder.index(LUMP_EDGES, start);
+            builder.index(LUMP_EDGES, end);
+        }
+
+        for surf_edge in [1i32, 2, 3, -4] {
+            builder.lumps[LUMP_SURFEDGES].extend(surf_edge.to_le_bytes());
+        }
+
+        let lump = &mut builder.lumps[LUMP_MODELS];
+        floats(lump, &[0.0, 0.0, -8.0, 16.0, 16.0, 8.0, 0.0, 0.0, 0.0]);
+        for value in [0i32, 0, 0, 0, 1, 0, 1] {
+            lump.extend(value.to_le_bytes());
+        }
+
+        builder
+    }
+
+    /// A 16x16 texture, with a palette for GoldSrc maps
+    pub fn texture(&self) -> Vec<u8> {
+        let mut lump = Vec::new();
+        lump.extend(1u32.to_le_bytes());
+        lump.extend(8u32.to_le_bytes());
+
+        lump.extend(b"test\0\0\0\0\0\0\0\0\0\0\0\0");
+        lump.extend(16u32.to_le_bytes());
+        lump.extend(16u32.to_le_bytes());
+        for offset in [40u32, 40 + 256, 40 + 256 + 64, 40 + 256 + 64 + 16] {
+            lump.extend(offset.to_le_bytes());
+        }
+        for size in [256, 64, 16, 4] {
+            lump.extend((0..size).map(|i| (i % 2) as u8));
+        }
+        if self.format.is_goldsrc() {
+            lump.extend(2u16.to_le_bytes());
+            lump.extend([255, 0, 0, 0, 0, 255]);
+        }
+
+        lump
+    }
+
+    pub fn signed(&mut self, lump: usize, value: i32) {
+        if self.format.has_wide_indices() {
+            self.lumps[lump].extend(value.to_le_bytes());
+        } else {
+            self.lumps[lump].extend((value as i16).to_le_bytes());
+        }
+    }
+
+    pub fn index(&mut self, lump: usize, value: u32) {
+        if self.format.has_wide_indices() {
+            self.lumps[lump].extend(value.to_le_bytes());
+        } else {
+            self.lumps[lump].extend((value as u16).to_le_bytes());
+        }
+    }
+
+    pub fn bounds(&mut self, lump: usize, values: [i16; 3]) {
+        for value in values {
+            if self.format.has_float_bounds() {
+                self.lumps[lump].extend((value as f32).to_le_bytes());
+            } else {
+                self.lumps[lump].extend(value.to_le_bytes());
+            }
+        }
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let version = match self.format {
+            BspFormat::Bsp29 => 29,
+            BspFormat::Bsp30 => 30,
+            BspFormat::Bsp2 => BSP2_VERSION,
+            BspFormat::Bsp2Rmq => BSP2_RMQ_VERSION,
+        };
+
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.resize(4 + HEADER_LUMPS * 8, 0);
+        for (i, lump) in self.lumps.iter().enumerate() {
+            let offset = bytes.len() as u32;
+            bytes[4 + i * 8..8 + i * 8].copy_from_slice(&offset.to_le_bytes());
+            bytes[8 + i * 8..12 + i * 8].copy_from_slice(&(lump.len() as u32).to_le_bytes());
+            bytes.extend(lump);
+            bytes.resize((bytes.len() + 3) & !3, 0);
+        }
+
+        bytes
+    }
+}
+
+pub fn floats(lump: &mut Vec<u8>, values: &[f32]) {
+    for value in values {
+        lump.extend(value.to_le_bytes());
+    }
+}
+
+pub const FORMATS: [BspFormat; 4] = [
+    BspFormat::Bsp29,
+    BspFormat::Bsp30,
+    BspFormat::Bsp2,
+    BspFormat::Bsp2Rmq,
+];
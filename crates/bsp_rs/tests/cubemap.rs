@@ -0,0 +1,171 @@
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use bsp_rs::*;
+use common::Builder;
+use image::{ImageError, Rgba, RgbaImage};
+
+mod common;
+
+const SIDES: [&str; 6] = ["rt", "lf", "up", "dn", "bk", "ft"];
+
+/// An empty directory of the system's temporary one
+fn temp_dir(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("bsp_rs_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Writes a 1x1 sky side of a single shade into a game directory
+fn write_side(game: &Path, side: &str, extension: &str, shade: u8) {
+    let directory = game.join(SKY_DIRECTORY);
+    fs::create_dir_all(&directory).unwrap();
+    RgbaImage::from_pixel(1, 1, Rgba([shade, shade, shade, 255]))
+        .save(directory.join(format!("{}.{}", side, extension)))
+        .unwrap();
+}
+
+/// Shades of the sides of a cubemap, in file order
+fn shades(cubemap: &Cubemap) -> Vec<u8> {
+    cubemap.sides.iter().map(|side| side[0]).collect()
+}
+
+#[test]
+fn reads_cubemaps_with_and_without_options() {
+    let directory = temp_dir("cubemap");
+    for (side, size) in [
+        ("rt", 2),
+        ("lf", 2),
+        ("up", 2),
+        ("dn", 2),
+        ("bk", 2),
+        ("ft", 4),
+    ] {
+        RgbaImage::new(size, size)
+            .save(directory.join(format!("sky{}.tga", side)))
+            .unwrap();
+    }
+
+    let result = read_cubemap("sky", &directory);
+    assert!(matches!(
+        result,
+        Err(ImageError::IoError(ref err)) if err.kind() == ErrorKind::InvalidData
+    ));
+    assert!(matches!(
+        read_cubemap("missing", &directory),
+        Err(ImageError::IoError(ref err)) if err.kind() == ErrorKind::NotFound
+    ));
+
+    let options = CubemapOptions {
+        resample: true,
+        dimension: None,
+    };
+    let cubemap = read_cubemap_with("sky", &directory, &options).unwrap();
+    assert_eq!(cubemap.dimension, 4);
+    assert!(cubemap.sides.iter().all(|side| side.len() == 4 * 4 * 4));
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn reads_skyboxes_of_game_directories() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_ENTITIES] =
+        b"{\n\"classname\" \"worldspawn\"\n\"skyname\" \"night\"\n}\n\0".to_vec();
+    let bsp = read_bsp(&builder.build()).unwrap();
+    assert_eq!(bsp.sky_name(), "night");
+
+    let root = temp_dir("skybox");
+    let (valve, valve_hd, game) = (root.join("valve"), root.join("valve_hd"), root.join("mod"));
+    for side in SIDES {
+        write_side(&valve, &format!("night{}", side), "tga", 10);
+    }
+    write_side(&valve_hd, "nightup", "tga", 20);
+    // Bitmaps are only used without a TGA
+    write_side(&valve_hd, "nightup", "bmp", 30);
+    write_side(&valve_hd, "nightdn", "bmp", 30);
+    write_side(&game, "nightrt", "tga", 40);
+
+    let options = CubemapOptions::default();
+    let cubemap = bsp.skybox(&[&valve], false, &options).unwrap();
+    assert_eq!(shades(&cubemap), [10, 10, 10, 10, 10, 10]);
+
+    let cubemap = bsp.skybox(&[&valve], true, &options).unwrap();
+    assert_eq!(shades(&cubemap), [10, 10, 20, 30, 10, 10]);
+
+    // Each side comes from the first directory having it
+    let cubemap = bsp.skybox(&[&game, &valve], true, &options).unwrap();
+    assert_eq!(shades(&cubemap), [40, 10, 20, 30, 10, 10]);
+
+    assert!(matches!(
+        bsp.skybox(&[&game], false, &options),
+        Err(CubemapError::MissingSide(side)) if side == "nightlf"
+    ));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn defaults_to_the_desert_sky() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+    assert_eq!(bsp.sky_name(), DEFAULT_SKY_NAME);
+
+    let root = temp_dir("default_sky");
+    let valve = root.join("valve");
+    for side in SIDES {
+        write_side(&valve, &format!("desert{}", side), "bmp", 50);
+    }
+
+    let cubemap = bsp
+        .skybox(&[&valve], true, &CubemapOptions::default())
+        .unwrap();
+    assert_eq!(shades(&cubemap), [50; 6]);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+/// A 3x3 cubemap whose sides each have their own shade, 10 for `rt` up to
+/// 60 for `ft`
+fn shaded_cubemap() -> Cubemap {
+    let side = |shade: u8| [shade, shade, shade, 255].repeat(9);
+    Cubemap {
+        dimension: 3,
+        sides: [side(10), side(20), side(30), side(40), side(50), side(60)],
+    }
+}
+
+#[test]
+fn reorders_sides_into_faces() {
+    let cubemap = shaded_cubemap();
+
+    // +X, -X, +Y, -Y, +Z, -Z
+    for (layout, expected) in [
+        (CubemapLayout::RightHandedYUp, [10, 20, 30, 40, 60, 50]),
+        (CubemapLayout::LeftHandedYUp, [10, 20, 30, 40, 50, 60]),
+    ] {
+        let faces = cubemap.faces(layout);
+        assert!(faces.iter().all(|face| face.len() == 3 * 3 * 4));
+        let centers: Vec<_> = faces.iter().map(|face| face[4 * 4]).collect();
+        assert_eq!(centers, expected, "{:?}", layout);
+    }
+}
+
+#[test]
+fn projects_panoramas() {
+    let pixels = shaded_cubemap().to_equirectangular(8);
+    assert_eq!(pixels.len(), 8 * 4 * 4);
+    let shade = |column: usize, row: usize| pixels[(row * 8 + column) * 4];
+
+    // +X in the middle, +Y to its left and -X at the edges
+    assert_eq!(shade(4, 2), 10);
+    assert_eq!(shade(2, 2), 50);
+    assert_eq!(shade(6, 2), 60);
+    assert_eq!(shade(0, 2), 20);
+    // Up at the top, down at the bottom
+    assert_eq!(shade(4, 0), 30);
+    assert_eq!(shade(4, 3), 40);
+}
@@ -0,0 +1,126 @@
+use bsp_rs::*;
+
+#[test]
+fn keeps_braces_inside_quoted_values() {
+    let entities = parse_entities("{\n\"message\" \"{ not } an entity\"\n}\n").unwrap();
+
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].get("message"), Some("{ not } an entity"));
+}
+
+#[test]
+fn keeps_duplicate_keys_in_order() {
+    let entities =
+        parse_entities("{\n\"target\" \"a\"\n\"wad\" \"x.wad\"\n\"target\" \"b\"\n}\n").unwrap();
+    let entity = &entities[0];
+
+    assert_eq!(
+        entity.iter().collect::<Vec<_>>(),
+        [("target", "a"), ("wad", "x.wad"), ("target", "b")]
+    );
+    assert_eq!(entity.get("target"), Some("b"));
+    assert_eq!(entity.get_all("target").collect::<Vec<_>>(), ["a", "b"]);
+}
+
+#[test]
+fn skips_comments() {
+    let data = "// first\n{\n\"classname\" \"worldspawn\" // trailing\n// \"skipped\" \"key\"\n}\n";
+    let entities = parse_entities(data).unwrap();
+
+    assert_eq!(
+        entities[0].iter().collect::<Vec<_>>(),
+        [("classname", "worldspawn")]
+    );
+}
+
+#[test]
+fn reports_error_positions() {
+    let error = parse_entities("{\n  \"classname\" \"light\n}\n").unwrap_err();
+    assert_eq!(
+        error,
+        EntityParseError {
+            line: 2,
+            column: 15,
+            kind: EntityParseErrorKind::UnterminatedString,
+        }
+    );
+
+    let error = parse_entities("{\n\"classname\" \"light\"\n\"style\"\n}\n").unwrap_err();
+    assert_eq!(
+        error,
+        EntityParseError {
+            line: 4,
+            column: 1,
+            kind: EntityParseErrorKind::MissingValue("style".to_string()),
+        }
+    );
+    assert_eq!(error.to_string(), "4:1: key \"style\" has no value");
+
+    let error = parse_entities("{\n\"classname\" \"light\"\n").unwrap_err();
+    assert_eq!(error.kind, EntityParseErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn parses_typed_values() {
+    let entities = parse_entities(
+        "{\n\"origin\" \"1 -2.5 3\"\n\"_light\" \"255 128 0 200\"\n\"speed\" \" 1.5 \"\n\"spawnflags\" \"3.9\"\n\"angles\" \"0 90\"\n}\n",
+    )
+    .unwrap();
+    let entity = &entities[0];
+
+    assert_eq!(entity.get_vec3("origin"), Some(Vec3::new(1.0, -2.5, 3.0)));
+    assert_eq!(
+        entity.get_color("_light"),
+        Some(Rgb {
+            r: 255,
+            g: 128,
+            b: 0
+        })
+    );
+    assert_eq!(entity.get_f32("speed"), Some(1.5));
+    assert_eq!(entity.get_i32("spawnflags"), Some(3));
+    assert_eq!(entity.get_vec3("angles"), None);
+    assert_eq!(entity.get_f32("missing"), None);
+}
+
+#[test]
+fn round_trips_written_entities() {
+    let mut world = Entity::new("worldspawn");
+    world.set("wad", "\\half-life\\valve\\halflife.wad");
+    let mut light = Entity::new("light");
+    light.set_vec3("origin", Vec3::new(-0.0, 12.5, 1e-3));
+    light.set_color(
+        "_light",
+        Rgb {
+            r: 255,
+            g: 200,
+            b: 0,
+        },
+    );
+    light.set_f32("_fade", 0.1);
+    light
+        .properties
+        .push(("target".to_string(), "{braces}".to_string()));
+    light
+        .properties
+        .push(("target".to_string(), "".to_string()));
+    let entities = vec![world, light];
+
+    let data = write_entities(&entities).unwrap();
+    assert!(data.contains("\"origin\" \"0 12.5 0.001\""));
+    assert_eq!(parse_entities(&data).unwrap(), entities);
+}
+
+#[test]
+fn rejects_double_quotes() {
+    let mut entity = Entity::new("info_target");
+    entity.set("message", "say \"hi\"");
+
+    assert_eq!(
+        write_entities(&[Entity::new("worldspawn"), entity]),
+        Err(EntityWriteError {
+            entity: 1,
+            key: "message".to_string(),
+        })
+    );
+}
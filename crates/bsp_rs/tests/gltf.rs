@@ -0,0 +1,102 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+/// Splits a GLB file into its JSON and binary chunks, checking the header
+fn chunks(glb: &[u8]) -> (String, &[u8]) {
+    let u32_at = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(u32_at(4), 2);
+    assert_eq!(u32_at(8) as usize, glb.len());
+
+    let json_length = u32_at(12) as usize;
+    assert_eq!(json_length % 4, 0);
+    assert_eq!(&glb[16..20], b"JSON");
+    let json = String::from_utf8(glb[20..20 + json_length].to_vec()).unwrap();
+
+    let bin = 20 + json_length;
+    let bin_length = u32_at(bin) as usize;
+    assert_eq!(&glb[bin + 4..bin + 8], b"BIN\0");
+    assert_eq!(bin + 8 + bin_length, glb.len());
+
+    (json, &glb[bin + 8..])
+}
+
+#[test]
+fn writes_glb_chunks() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+    let glb = bsp.write_glb(&GltfOptions { scale: 0.5 });
+
+    let (json, bin) = chunks(&glb);
+    let json = json.trim_end_matches(' ');
+    assert!(json.starts_with("{\"asset\":{\"version\":\"2.0\""));
+    assert!(json.ends_with('}'));
+    assert!(json.contains(&format!("\"buffers\":[{{\"byteLength\":{}}}]", bin.len())));
+
+    // The square face, scaled and turned Y up
+    assert!(json.contains("\"min\":[0,0,-8],\"max\":[8,0,0]"));
+    assert!(json.contains("\"TEXCOORD_0\":2,\"TEXCOORD_1\":3"));
+
+    // The lightmap atlas is the first texture, the embedded one the second
+    assert!(json.contains(
+        "\"baseColorTexture\":{\"index\":1},\"metallicFactor\":0},\"extras\":{\"lightmap\":{\"index\":0,\"texCoord\":1}}"
+    ));
+    assert!(!json.contains("occlusionTexture"));
+}
+
+/// Reads the 32 bit values of a buffer view, views being listed in buffer order
+fn view_words(json: &str, bin: &[u8], view: usize) -> Vec<u32> {
+    let view = json.split("\"byteOffset\":").nth(view + 1).unwrap();
+    let number = |text: &str| {
+        let end = text.find(|c: char| !c.is_ascii_digit()).unwrap();
+        text[..end].parse::<usize>().unwrap()
+    };
+    let offset = number(view);
+    let length = number(view.split("\"byteLength\":").nth(1).unwrap());
+
+    bin[offset..offset + length]
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn winds_triangles_counter_clockwise() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+    let glb = bsp.write_glb(&GltfOptions::default());
+    let (json, bin) = chunks(&glb);
+
+    // Both textures come first, then positions, normals, both UVs and indices
+    let vec3 = |words: Vec<u32>| -> Vec<Vec3> {
+        let floats: Vec<_> = words.into_iter().map(f32::from_bits).collect();
+        floats
+            .chunks_exact(3)
+            .map(|c| Vec3::new(c[0], c[1], c[2]))
+            .collect()
+    };
+    let positions = vec3(view_words(&json, bin, 2));
+    let normals = vec3(view_words(&json, bin, 3));
+    let indices: Vec<_> = view_words(&json, bin, 6)
+        .into_iter()
+        .map(|index| index as usize)
+        .collect();
+    assert_eq!(indices, [0, 2, 1, 0, 3, 2]);
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i]]);
+        let geometric = (b - a).cross(c - a).normalize();
+        assert!(geometric.dot(normals[triangle[0]]) > 0.99);
+    }
+}
+
+#[test]
+fn scales_units_to_meters() {
+    assert_eq!(GltfOptions::default().scale, UNIT_SIZE);
+
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+    let (json, _) = chunks(&bsp.write_glb(&GltfOptions { scale: 1.0 }));
+    assert!(json.contains("\"min\":[0,0,-16],\"max\":[16,0,0]"));
+}
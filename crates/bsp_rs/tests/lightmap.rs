@@ -0,0 +1,87 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+/// The fixture with its face repeated three times, all sharing one 2x2 lightmap
+fn bsp() -> Bsp {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    let face = builder.lumps[LUMP_FACES].clone();
+    builder.lumps[LUMP_FACES].extend(face.repeat(2));
+    read_bsp(&builder.build()).unwrap()
+}
+
+fn pixel(atlas: &LightmapAtlas, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * atlas.width + x) * 4) as usize;
+    atlas.pixels[offset..offset + 4].try_into().unwrap()
+}
+
+#[test]
+fn packs_lightmaps_with_gutters() {
+    let atlas = bsp().lightmap_atlas();
+    assert_eq!((atlas.width, atlas.height), (8, 8));
+
+    let rect = |x, y| {
+        Some(AtlasRect {
+            x,
+            y,
+            width: 2,
+            height: 2,
+        })
+    };
+    assert_eq!(atlas.rects, [rect(1, 1), rect(5, 1), rect(1, 5)]);
+
+    assert_eq!(pixel(&atlas, 1, 1), [0, 1, 2, 255]);
+    assert_eq!(pixel(&atlas, 2, 2), [9, 10, 11, 255]);
+    // The gutter repeats the closest sample
+    assert_eq!(pixel(&atlas, 0, 0), [0, 1, 2, 255]);
+    assert_eq!(pixel(&atlas, 3, 0), [3, 4, 5, 255]);
+    assert_eq!(pixel(&atlas, 0, 3), [6, 7, 8, 255]);
+    assert_eq!(pixel(&atlas, 3, 3), [9, 10, 11, 255]);
+    assert_eq!(pixel(&atlas, 4, 0), [0, 1, 2, 255]);
+    assert_eq!(pixel(&atlas, 5, 5), [0; 4]);
+}
+
+#[test]
+fn maps_lightmap_coordinates_into_the_atlas() {
+    let atlas = bsp().lightmap_atlas();
+
+    assert_eq!(atlas.uv(0, [0.0, 0.0]), Some([0.125, 0.125]));
+    assert_eq!(atlas.uv(1, [0.5, 1.0]), Some([0.75, 0.375]));
+    assert_eq!(atlas.uv(2, [1.0, 0.25]), Some([0.375, 0.6875]));
+    assert_eq!(atlas.uv(3, [0.0, 0.0]), None);
+}
+
+#[test]
+fn writes_face_samples() {
+    let mut atlas = bsp().lightmap_atlas();
+    let samples = [255, 128, 64, 0].map(|v| Rgb { r: v, g: v, b: v });
+
+    atlas.write(2, &samples);
+    assert_eq!(pixel(&atlas, 1, 5), [255, 255, 255, 255]);
+    assert_eq!(pixel(&atlas, 2, 6), [0, 0, 0, 255]);
+    assert_eq!(pixel(&atlas, 0, 7), [64, 64, 64, 255]);
+    assert_eq!(pixel(&atlas, 3, 4), [128, 128, 128, 255]);
+    // Other faces are left alone
+    assert_eq!(pixel(&atlas, 1, 1), [0, 1, 2, 255]);
+
+    // Too few samples for the face
+    atlas.write(0, &samples[..3]);
+    assert_eq!(pixel(&atlas, 1, 1), [0, 1, 2, 255]);
+}
+
+#[test]
+fn rejects_bad_surface_extents() {
+    assert_eq!(BspFormat::Bsp30.max_surface_extents(), 256);
+    assert_eq!(BspFormat::Bsp29.max_surface_extents(), 512);
+
+    // 32 texels per unit stretch the 16 unit face over 512 texels
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_TEXINFO][0..4].copy_from_slice(&32f32.to_le_bytes());
+    builder.lumps[LUMP_LIGHTING] = vec![0; 33 * 2 * 3];
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    assert!(bsp.face_lightmap(&bsp.faces[0]).is_none());
+    assert_eq!(bsp.lightmap_atlas().rects, [None]);
+}
@@ -0,0 +1,71 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+const LIGHTS: &str = r#"
+{ "classname" "worldspawn" }
+{ "classname" "light" "style" "32" "pattern" "az" }
+{ "classname" "light_spot" "style" "33" "spawnflags" "1" "pattern" "z" }
+{ "classname" "light" "style" "34" }
+{ "classname" "light" "style" "5" "pattern" "z" }
+{ "classname" "light" "style" "64" "pattern" "z" }
+{ "classname" "info_target" "style" "35" "pattern" "z" }
+"#;
+
+#[test]
+fn missing_styles_use_normal_brightness() {
+    let mut styles = LightStyles::default();
+    styles.set(40, "az");
+
+    assert_eq!(NORMAL_LIGHT_STYLE_VALUE, 256);
+    assert_eq!(styles.value(0, 0.0), 264);
+    assert_eq!(styles.value(20, 0.0), NORMAL_LIGHT_STYLE_VALUE);
+    assert_eq!(styles.value(200, 0.0), NORMAL_LIGHT_STYLE_VALUE);
+    assert_eq!(styles.value(40, 0.0), 0);
+    assert_eq!(styles.value(40, 0.1), 550);
+}
+
+#[test]
+fn applies_switchable_light_styles() {
+    let styles = LightStyles::from_entities(&parse_entities(LIGHTS).unwrap());
+
+    assert_eq!(styles.patterns[32], "az");
+    // Lights starting off are dark until switched on
+    assert_eq!(styles.patterns[33], "a");
+    assert_eq!(styles.patterns[34], "m");
+    // Styles below 32 belong to the game dll, 64 and up don't exist
+    assert_eq!(styles.patterns[5], DEFAULT_LIGHT_STYLES[5].1);
+    assert_eq!(styles.patterns.len(), MAX_LIGHTSTYLES);
+    assert_eq!(styles.patterns[35], "");
+}
+
+#[test]
+fn composes_light_styles() {
+    // Style 0 at 100 and a switchable style at 10 over the 2x2 samples
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_FACES][13] = 32;
+    builder.lumps[LUMP_LIGHTING] = [vec![100; 12], vec![10; 12]].concat();
+    let bsp = read_bsp(&builder.build()).unwrap();
+    let face = &bsp.faces[0];
+
+    let compose = |styles: &LightStyles, time| {
+        let samples = bsp.compose_lightmap(face, styles, time).unwrap();
+        assert_eq!(samples.len(), 4);
+        assert!(samples.iter().all(|s| s == &samples[0]));
+        samples[0].r
+    };
+
+    let mut styles = LightStyles::default();
+    // 100 * 264 + 10 * 256, divided by 256
+    assert_eq!(compose(&styles, 0.0), 113);
+
+    styles.set(32, "az");
+    assert_eq!(compose(&styles, 0.0), 103);
+    assert_eq!(compose(&styles, 0.1), 124);
+
+    styles.set(0, "z");
+    styles.set(32, "z");
+    assert_eq!(compose(&styles, 0.0), 236);
+}
@@ -0,0 +1,216 @@
+use bsp_rs::*;
+
+use common::{Builder, FORMATS};
+
+mod common;
+
+#[test]
+fn reads_every_lump() {
+    for format in FORMATS {
+        let bsp = read_bsp(&Builder::new(format).build()).unwrap();
+        assert_eq!(bsp.format, format);
+
+        assert_eq!(bsp.entities.len(), 1);
+        assert_eq!(bsp.entities[0].classname(), Some("worldspawn"));
+
+        assert_eq!(bsp.planes.len(), 2);
+        assert_eq!(bsp.planes[1].normal, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(bsp.planes[1].dist, 8.0);
+
+        assert_eq!(bsp.textures.len(), 1);
+        assert_eq!(bsp.textures[0].name, "test");
+        assert_eq!(bsp.textures[0].indices.len(), 4);
+        assert_eq!(bsp.textures[0].indices[3].len(), 4);
+        assert_eq!(
+            bsp.textures[0].palette.len(),
+            format.is_goldsrc() as usize * 2
+        );
+
+        assert_eq!(bsp.vertices.len(), 4);
+        assert_eq!(bsp.vertices[2], Vec3::new(16.0, 16.0, 0.0));
+
+        assert_eq!(bsp.vis_size, 3);
+
+        assert_eq!(bsp.nodes.len(), 1);
+        assert_eq!(bsp.nodes[0].children, [-2, -1]);
+        assert_eq!(bsp.nodes[0].mins, [0.0, 0.0, -8.0]);
+        assert_eq!(bsp.nodes[0].maxs, [16.0, 16.0, 8.0]);
+        assert_eq!((bsp.nodes[0].first_face, bsp.nodes[0].num_faces), (0, 1));
+
+        assert_eq!(bsp.texture_infos.len(), 1);
+        assert_eq!(bsp.texture_infos[0].s_shift, 16.0);
+        assert_eq!(bsp.texture_infos[0].t_shift, 32.0);
+
+        assert_eq!(bsp.faces.len(), 1);
+        assert_eq!(bsp.faces[0].plane_side, 1);
+        assert_eq!(bsp.faces[0].edges, 4);
+        assert_eq!(bsp.faces[0].styles, [0, 255, 255, 255]);
+
+        assert_eq!(bsp.lightmap.len(), 12);
+        let lightmap = bsp.face_lightmap(&bsp.faces[0]).unwrap();
+        assert_eq!((lightmap.width, lightmap.height), (2, 2));
+
+        assert_eq!(bsp.clip_nodes.len(), 1);
+        assert_eq!(bsp.clip_nodes[0].children, [-1, -2]);
+
+        assert_eq!(bsp.leaves.len(), 2);
+        assert!(matches!(bsp.leaves[0].contents, LeafContent::Solid));
+        assert!(matches!(bsp.leaves[1].contents, LeafContent::Empty));
+        assert_eq!(bsp.leaves[1].mark_surface, 1);
+        assert_eq!(bsp.leaves[1].ambient_levels, [1, 2, 3, 4]);
+
+        assert_eq!(bsp.mark_surfaces, [0]);
+
+        assert_eq!(bsp.edges.len(), 5);
+        assert_eq!((bsp.edges[4].start, bsp.edges[4].end), (0, 3));
+
+        assert_eq!(bsp.surf_edges, [1, 2, 3, -4]);
+
+        assert_eq!(bsp.models.len(), 1);
+        assert_eq!(bsp.models[0].faces, 1);
+    }
+}
+
+#[test]
+fn expands_monochrome_lighting() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp29).build()).unwrap();
+
+    assert_eq!(&bsp.lightmap[..6], &[0, 0, 0, 1, 1, 1]);
+}
+
+#[test]
+fn rejects_partial_records() {
+    for format in FORMATS {
+        for lump in [
+            LUMP_PLANES,
+            LUMP_VERTICES,
+            LUMP_NODES,
+            LUMP_TEXINFO,
+            LUMP_FACES,
+            LUMP_CLIPNODES,
+            LUMP_LEAVES,
+            LUMP_MARKSURFACES,
+            LUMP_EDGES,
+            LUMP_SURFEDGES,
+            LUMP_MODELS,
+        ] {
+            let mut builder = Builder::new(format);
+            builder.lumps[lump].push(0);
+
+            assert!(
+                read_bsp(&builder.build()).is_err(),
+                "lump {} of {:?}",
+                lump,
+                format
+            );
+        }
+    }
+}
+
+#[test]
+fn replaces_raw_lumps() {
+    let bytes = Builder::new(BspFormat::Bsp30).build();
+    let mut lumps = read_bsp_lumps(&bytes).unwrap();
+    assert_eq!(lumps.version, 30);
+    assert_eq!(lumps.lump(LUMP_VISIBILITY), [0xff; 3]);
+    assert_eq!(lumps.write(), bytes);
+
+    lumps.replace(LUMP_VISIBILITY, vec![1; 5]);
+    let bytes = lumps.write();
+    let bsp = read_bsp(&bytes).unwrap();
+    assert_eq!(bsp.vis_size, 5);
+    assert_eq!(bsp.surf_edges, [1, 2, 3, -4]);
+
+    let lumps = read_bsp_lumps(&bytes).unwrap();
+    assert_eq!(lumps.lump(LUMP_VISIBILITY), [1; 5]);
+    assert_eq!(lumps.entries[LUMP_VISIBILITY].offset % 4, 0);
+}
+
+#[test]
+fn keeps_bspx_lumps() {
+    let mut bytes = Builder::new(BspFormat::Bsp30).build();
+    let bspx = bytes.len() as u32;
+    bytes.extend(b"BSPX");
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(b"LMSHIFT\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+    bytes.extend((bspx + 40).to_le_bytes());
+    bytes.extend(1u32.to_le_bytes());
+    bytes.push(4);
+
+    let mut lumps = read_bsp_lumps(&bytes).unwrap();
+    assert_eq!(lumps.trailing(), &bytes[bspx as usize..]);
+    assert_eq!(lumps.write(), bytes);
+
+    lumps.replace(LUMP_VISIBILITY, vec![1; 5]);
+    let bytes = lumps.write();
+    let trailing = read_bsp_lumps(&bytes).unwrap().trailing().to_vec();
+    assert!(trailing.starts_with(b"BSPX"));
+
+    // The LMSHIFT lump moved along with the rest
+    let offset = u32::from_le_bytes(trailing[32..36].try_into().unwrap()) as usize;
+    assert_eq!(offset, bytes.len() - 1);
+    assert_eq!(bytes[offset], 4);
+}
+
+#[test]
+fn rejects_lumps_past_the_end() {
+    let mut bytes = Builder::new(BspFormat::Bsp30).build();
+    let length = bytes.len() as u32;
+    bytes[8 + LUMP_MODELS * 8..12 + LUMP_MODELS * 8].copy_from_slice(&length.to_le_bytes());
+
+    assert!(read_bsp_lumps(&bytes).is_err());
+}
+
+#[test]
+fn reads_blue_shift_maps() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps.swap(LUMP_ENTITIES, LUMP_PLANES);
+    let bytes = builder.build();
+
+    let bsp = read_bsp(&bytes).unwrap();
+    assert_eq!(bsp.variant, BspVariant::BlueShift);
+    assert_eq!(bsp.entities[0].classname(), Some("worldspawn"));
+    assert_eq!(bsp.planes.len(), 2);
+    assert_eq!(bsp.planes[1].dist, 8.0);
+
+    let ent = export_ent(&bsp).unwrap();
+    let ent = ent.replace("worldspawn\"", "worldspawn\"\n\"message\" \"Blue Shift\"");
+    let bytes = import_ent(&bytes, &ent).unwrap();
+
+    let bsp = read_bsp(&bytes).unwrap();
+    assert_eq!(bsp.variant, BspVariant::BlueShift);
+    assert_eq!(bsp.entities[0].get("message"), Some("Blue Shift"));
+    assert_eq!(bsp.planes[1].dist, 8.0);
+    assert_eq!(export_ent(&bsp).unwrap(), ent);
+
+    let lumps = read_bsp_lumps(&bytes).unwrap();
+    assert!(lumps.lump(LUMP_PLANES).starts_with(b"{"));
+}
+
+#[test]
+fn reads_missing_textures() {
+    for format in FORMATS {
+        let mut builder = Builder::new(format);
+        let mut lump = builder.texture();
+        lump[0..4].copy_from_slice(&2u32.to_le_bytes());
+        lump[4..8].copy_from_slice(&12u32.to_le_bytes());
+        lump.splice(8..8, u32::MAX.to_le_bytes());
+        builder.lumps[LUMP_TEXTURES] = lump;
+
+        let bsp = read_bsp(&builder.build()).unwrap();
+        assert_eq!(bsp.textures.len(), 2);
+        assert_eq!(bsp.textures[0].name, "test");
+        assert_eq!(bsp.textures[1].name, "");
+        assert!(bsp.textures[1].indices.is_empty());
+    }
+}
+
+#[test]
+fn treats_overflowing_lightmap_offsets_as_missing() {
+    let mut builder = Builder::new(BspFormat::Bsp29);
+    builder.lumps[LUMP_FACES][16..20].copy_from_slice(&0x6000_0000u32.to_le_bytes());
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    assert_eq!(bsp.faces[0].lightmap_offset, u32::MAX);
+    assert!(bsp.face_lightmap(&bsp.faces[0]).is_none());
+}
@@ -0,0 +1,76 @@
+use bsp_rs::*;
+
+use common::{Builder, FORMATS};
+
+mod common;
+
+#[test]
+fn triangulates_faces() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+
+    let mesh = bsp.model_mesh(0);
+    assert_eq!(mesh.groups.len(), 1);
+    let group = &mesh.groups[0];
+    assert_eq!(group.texture, 0);
+    assert_eq!(group.indices, [0, 1, 2, 0, 2, 3]);
+
+    let positions: Vec<_> = group.vertices.iter().map(|v| v.position).collect();
+    assert_eq!(
+        positions,
+        [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(16.0, 0.0, 0.0),
+            Vec3::new(16.0, 16.0, 0.0),
+            Vec3::new(0.0, 16.0, 0.0),
+        ]
+    );
+    // The face is on the back of its plane
+    assert!(group
+        .vertices
+        .iter()
+        .all(|v| v.normal == Vec3::new(0.0, 0.0, -1.0) && v.face == 0));
+}
+
+#[test]
+fn generates_texture_coordinates() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+
+    let vertices = &bsp.model_mesh(0).groups[0].vertices;
+    // Shifted by 16 and 32 texels on a 16x16 texture
+    assert_eq!(vertices[0].uv, [1.0, 2.0]);
+    assert_eq!(vertices[2].uv, [2.0, 3.0]);
+    // A single lightmap cell, with half a sample of border on each side
+    assert_eq!(vertices[0].lightmap_uv, [0.25, 0.25]);
+    assert_eq!(vertices[2].lightmap_uv, [0.75, 0.75]);
+}
+
+#[test]
+fn skips_malformed_faces() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_SURFEDGES][12..16].copy_from_slice(&9i32.to_le_bytes());
+    let bsp = read_bsp(&builder.build()).unwrap();
+    assert_eq!(bsp.face_vertices(&bsp.faces[0]).count(), 0);
+    assert!(bsp.model_mesh(0).groups.is_empty());
+
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_FACES][4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    let bsp = read_bsp(&builder.build()).unwrap();
+    assert_eq!(bsp.face_vertices(&bsp.faces[0]).count(), 0);
+    assert!(bsp.model_mesh(0).groups.is_empty());
+
+    assert!(bsp.model_mesh(1).groups.is_empty());
+}
+
+#[test]
+fn winds_triangles_clockwise_from_the_front() {
+    for format in FORMATS {
+        let bsp = read_bsp(&Builder::new(format).build()).unwrap();
+
+        let group = &bsp.model_mesh(0).groups[0];
+        for triangle in group.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| group.vertices[triangle[i] as usize]);
+            let normal = (b.position - a.position).cross(c.position - a.position);
+            assert!(normal.dot(a.normal) < 0.0, "{:?}", triangle);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+fn vertices(obj: &str) -> Vec<&str> {
+    obj.lines().filter(|line| line.starts_with("v ")).collect()
+}
+
+#[test]
+fn converts_up_axis_and_scale() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+
+    let export = bsp.to_obj("map.mtl", &ObjOptions::default());
+    assert!(export
+        .obj
+        .starts_with("mtllib map.mtl\no *0\nusemtl test\n"));
+    assert_eq!(
+        vertices(&export.obj),
+        ["v 0 0 0", "v 16 0 0", "v 16 0 -16", "v 0 0 -16"]
+    );
+    // Counter-clockwise when seen from below, where the face's normal points
+    assert!(export.obj.contains("vn 0 -1 0\n"));
+    assert!(export
+        .obj
+        .contains("f 1/1/1 3/3/3 2/2/2\nf 1/1/1 4/4/4 3/3/3\n"));
+
+    let options = ObjOptions {
+        up_axis: UpAxis::Z,
+        scale: 0.5,
+        ..Default::default()
+    };
+    let export = bsp.to_obj("map.mtl", &options);
+    assert_eq!(
+        vertices(&export.obj),
+        ["v 0 0 0", "v 8 0 0", "v 8 8 0", "v 0 8 0"]
+    );
+    assert!(export.obj.contains("vn 0 0 -1\n"));
+}
+
+#[test]
+fn skips_tool_textures() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_TEXTURES][8..12].copy_from_slice(b"CLIP");
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    let export = bsp.to_obj("map.mtl", &ObjOptions::default());
+    assert_eq!(export.obj, "mtllib map.mtl\no *0\n");
+    assert!(export.mtl.starts_with("newmtl CLIP\n"));
+
+    let options = ObjOptions {
+        skip_tool_textures: false,
+        ..Default::default()
+    };
+    let export = bsp.to_obj("map.mtl", &options);
+    assert!(export.obj.contains("usemtl CLIP\n"));
+    assert_eq!(vertices(&export.obj).len(), 4);
+}
@@ -0,0 +1,52 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+#[test]
+fn round_trips_entities() {
+    let bytes = Builder::new(BspFormat::Bsp30).build();
+    let bsp = read_bsp(&bytes).unwrap();
+
+    let ent = export_ent(&bsp).unwrap();
+    assert_eq!(ent, "{\n\"classname\" \"worldspawn\"\n}\n");
+
+    // Unchanged entities give back the same file
+    assert_eq!(import_ent(&bytes, &ent).unwrap(), bytes);
+
+    let ent = format!(
+        "{}{}",
+        ent, "{\n\"classname\" \"func_wall\"\n\"model\" \"*0\"\n}\n{\n\"classname\" \"cycler\"\n\"model\" \"models/g.mdl\"\n}\n"
+    );
+    let imported = read_bsp(&import_ent(&bytes, &ent).unwrap()).unwrap();
+    assert_eq!(imported.entities.len(), 3);
+    assert_eq!(imported.entities[1].get("model"), Some("*0"));
+    assert_eq!(export_ent(&imported).unwrap(), ent);
+    assert_eq!(imported.faces.len(), bsp.faces.len());
+}
+
+#[test]
+fn rejects_missing_models() {
+    let bytes = Builder::new(BspFormat::Bsp30).build();
+
+    for model in ["*1", "*wall", "*-1"] {
+        let ent = format!(
+            "{{\n\"classname\" \"worldspawn\"\n}}\n{{\n\"classname\" \"func_wall\"\n\"model\" \"{}\"\n}}\n",
+            model
+        );
+        let error = import_ent(&bytes, &ent).unwrap_err();
+        let RipentError::InvalidModel {
+            entity,
+            model: value,
+        } = &error
+        else {
+            panic!("{:?}", error);
+        };
+        assert_eq!((*entity, value.as_str()), (1, model));
+        assert_eq!(
+            error.to_string(),
+            format!("entity 1 references missing model \"{}\"", model)
+        );
+    }
+}
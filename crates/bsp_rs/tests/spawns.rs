@@ -0,0 +1,105 @@
+use bsp_rs::*;
+
+use common::Builder;
+
+mod common;
+
+/// Counter-terrorists right of the `x = 8` clip plane, terrorists left of
+/// it and under the floor
+const ENTITIES: &str = r#"{ "classname" "worldspawn" }
+{ "classname" "info_player_start" "origin" "12 8 0" }
+{ "classname" "info_player_deathmatch" "origin" "4 8 0" }
+{ "classname" "info_player_deathmatch" "origin" "12 8 -40" }
+{ "classname" "trigger_multiple" "model" "*0" }
+{ "classname" "func_buyzone" "model" "*0" "origin" "100 0 0" }
+{ "classname" "func_wall" "model" "*0" }
+"#;
+
+fn bsp(builder: &mut Builder) -> Bsp {
+    builder.lumps[LUMP_ENTITIES] = ENTITIES.bytes().chain([0]).collect();
+    read_bsp(&builder.build()).unwrap()
+}
+
+#[test]
+fn finds_stuck_spawns() {
+    let bsp = bsp(&mut Builder::new(BspFormat::Bsp30));
+    let analysis = bsp.analyze_spawns();
+
+    let spawns: Vec<_> = analysis
+        .spawns
+        .iter()
+        .map(|spawn| (spawn.entity_index, spawn.team, spawn.stuck))
+        .collect();
+    assert_eq!(
+        spawns,
+        [
+            (1, SpawnTeam::CounterTerrorist, false),
+            (2, SpawnTeam::Terrorist, true),
+            (3, SpawnTeam::Terrorist, false),
+        ]
+    );
+
+    // The visible hull only has the floor
+    assert!(!bsp.is_solid(0, Vec3::new(4.0, 8.0, 4.0)));
+    assert!(bsp.is_solid(0, Vec3::new(12.0, 8.0, -4.0)));
+}
+
+#[test]
+fn checks_lines_of_sight() {
+    let bsp = bsp(&mut Builder::new(BspFormat::Bsp30));
+
+    // Only the terrorist above the floor is in sight
+    assert_eq!(bsp.analyze_spawns().sight_lines, [(0, 1)]);
+
+    assert!(bsp.line_of_sight(Vec3::new(0.0, 0.0, 4.0), Vec3::new(16.0, 16.0, 8.0)));
+    assert!(!bsp.line_of_sight(Vec3::new(0.0, 0.0, 4.0), Vec3::new(0.0, 0.0, -4.0)));
+    assert!(!bsp.line_of_sight(Vec3::new(0.0, 0.0, -4.0), Vec3::new(16.0, 0.0, -8.0)));
+}
+
+#[test]
+fn finds_trigger_bounds() {
+    let bsp = bsp(&mut Builder::new(BspFormat::Bsp30));
+
+    let triggers: Vec<_> = bsp
+        .analyze_spawns()
+        .triggers
+        .into_iter()
+        .map(|trigger| {
+            (
+                trigger.entity_index,
+                trigger.classname,
+                trigger.bounds.min,
+                trigger.bounds.max,
+            )
+        })
+        .collect();
+    assert_eq!(
+        triggers,
+        [
+            (
+                4,
+                "trigger_multiple".to_string(),
+                Vec3::new(0.0, 0.0, -8.0),
+                Vec3::new(16.0, 16.0, 8.0),
+            ),
+            (
+                5,
+                "func_buyzone".to_string(),
+                Vec3::new(100.0, 0.0, -8.0),
+                Vec3::new(116.0, 16.0, 8.0),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn stops_at_looping_nodes() {
+    // Both the clip node and the node are their own front child
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_CLIPNODES][4..6].copy_from_slice(&0i16.to_le_bytes());
+    builder.lumps[LUMP_NODES][4..6].copy_from_slice(&0i16.to_le_bytes());
+    let bsp = bsp(&mut builder);
+
+    assert!(!bsp.is_solid(HUMAN_HULL, Vec3::new(12.0, 8.0, 0.0)));
+    assert!(!bsp.line_of_sight(Vec3::new(0.0, 0.0, 4.0), Vec3::new(16.0, 16.0, 8.0)));
+}
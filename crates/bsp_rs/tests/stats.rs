@@ -0,0 +1,73 @@
+use bsp_rs::*;
+
+use common::{floats, Builder};
+
+mod common;
+
+#[test]
+fn reports_stats() {
+    let stats = read_bsp_stats(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+
+    let faces = stats.lumps.iter().find(|l| l.lump == LUMP_FACES).unwrap();
+    assert_eq!((faces.count, faces.size), (Some(1), 20));
+    assert_eq!(stats.alloc_blocks, 1);
+    assert_eq!(
+        stats.textures,
+        [TextureUsage {
+            name: "test".to_string(),
+            faces: 1,
+            embedded: true,
+        }]
+    );
+    assert!(stats.to_json().contains("\"name\":\"faces\",\"count\":1"));
+}
+
+/// The fixture with `big` faces of 16x16 lightmap samples, followed by one
+/// of 2x2 samples
+fn alloc_blocks(big: usize) -> usize {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    let lump = &mut builder.lumps[LUMP_TEXINFO];
+    floats(lump, &[15.0, 0.0, 0.0, 16.0, 0.0, 15.0, 0.0, 16.0]);
+    lump.extend(0u32.to_le_bytes());
+    lump.extend(0u32.to_le_bytes());
+
+    let small = builder.lumps[LUMP_FACES].clone();
+    let mut face = small.clone();
+    face[10..12].copy_from_slice(&1u16.to_le_bytes());
+    builder.lumps[LUMP_FACES] = [face.repeat(big), small].concat();
+
+    read_bsp_stats(&builder.build()).unwrap().alloc_blocks
+}
+
+#[test]
+fn allocates_lightmap_blocks_like_the_engine() {
+    // 7 columns of 8 lightmaps, the last 16 texels never being tried
+    assert_eq!(alloc_blocks(0), 1);
+    assert_eq!(alloc_blocks(56), 1);
+    assert_eq!(alloc_blocks(57), 2);
+    // The small lightmap goes back to the free column of the first texture
+    assert_eq!(alloc_blocks(112), 2);
+}
+
+#[test]
+fn prints_stats() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_ENTITIES] =
+        b"{\n\"classname\" \"worldspawn\"\n\"wad\" \"\\valve\\halflife.wad; ;decals.wad\"\n}\n\0"
+            .to_vec();
+    let stats = read_bsp_stats(&builder.build()).unwrap();
+    assert_eq!(stats.wads, ["\\valve\\halflife.wad", "decals.wad"]);
+
+    let text = stats.to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Object names  Objects/Maxobjs  Memory / Maxmem  Fullness"
+    );
+    assert!(lines.contains(&"faces               1/65535         20/1310700  (  0.0%)"));
+    assert!(lines.contains(&"textures        [variable]         396/2097152  (  0.0%)"));
+    assert!(lines.contains(&"allocblock          1/64                        (  1.6%)"));
+    assert!(text.ends_with(
+        "Textures  Faces  Source\ntest                  1  bsp\n\nWads\n\\valve\\halflife.wad\ndecals.wad\n"
+    ));
+}
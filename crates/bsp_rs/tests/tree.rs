@@ -0,0 +1,135 @@
+use bsp_rs::*;
+
+use common::{floats, Builder};
+
+mod common;
+
+fn bounds(min: [f32; 3], max: [f32; 3]) -> BoundBox {
+    BoundBox {
+        min: Vec3::new(min[0], min[1], min[2]),
+        max: Vec3::new(max[0], max[1], max[2]),
+    }
+}
+
+#[test]
+fn finds_leaves_at_points() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    // No ambient sounds in the solid leaf
+    builder.lumps[LUMP_LEAVES][24..28].fill(0);
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    assert_eq!(bsp.leaf_at(Vec3::new(8.0, 8.0, 4.0)), 1);
+    // Points on a plane are behind it
+    assert_eq!(bsp.leaf_at(Vec3::new(8.0, 8.0, 0.0)), 0);
+    assert_eq!(bsp.leaf_at(Vec3::new(8.0, 8.0, -4.0)), 0);
+
+    let ambient = bsp.ambient_at(Vec3::new(8.0, 8.0, 4.0));
+    assert_eq!(
+        ambient,
+        AmbientLevels {
+            water: 1.0 / 255.0,
+            sky: 2.0 / 255.0,
+            slime: 3.0 / 255.0,
+            lava: 4.0 / 255.0,
+        }
+    );
+    assert_eq!(
+        bsp.ambient_at(Vec3::new(8.0, 8.0, -4.0)),
+        AmbientLevels::default()
+    );
+
+    let expected = bounds([0.0, 0.0, -8.0], [16.0, 16.0, 8.0]);
+    assert_eq!(bsp.leaves[1].bounds(), expected);
+    assert_eq!(bsp.nodes[0].bounds(), expected);
+}
+
+#[test]
+fn stops_at_looping_nodes() {
+    // The node is its own front child
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_NODES][4..6].copy_from_slice(&0i16.to_le_bytes());
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    assert_eq!(bsp.leaf_at(Vec3::new(8.0, 8.0, 4.0)), 0);
+    assert_eq!(bsp.leaf_at(Vec3::new(8.0, 8.0, -4.0)), 0);
+}
+
+#[test]
+fn finds_leaves_in_boxes() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+
+    assert_eq!(
+        bsp.leaves_in_box(&bounds([0.0, 0.0, 1.0], [4.0, 4.0, 4.0])),
+        [1]
+    );
+    assert_eq!(
+        bsp.leaves_in_box(&bounds([0.0, 0.0, -4.0], [4.0, 4.0, 4.0])),
+        [1]
+    );
+    // Touching the plane from behind reaches the leaf in front
+    assert_eq!(
+        bsp.leaves_in_box(&bounds([0.0, 0.0, -4.0], [4.0, 4.0, 0.0])),
+        [1]
+    );
+    // The solid leaf is left out
+    assert!(bsp
+        .leaves_in_box(&bounds([0.0, 0.0, -4.0], [4.0, 4.0, -1.0]))
+        .is_empty());
+    // Outside the bounds of the root node
+    assert!(bsp
+        .leaves_in_box(&bounds([32.0, 0.0, 1.0], [40.0, 4.0, 4.0]))
+        .is_empty());
+}
+
+#[test]
+fn finds_faces_in_boxes() {
+    let bsp = read_bsp(&Builder::new(BspFormat::Bsp30).build()).unwrap();
+
+    assert_eq!(
+        bsp.faces_in_box(&bounds([4.0, 4.0, -4.0], [8.0, 8.0, 4.0])),
+        [0]
+    );
+    // Boxes touching the face from either side
+    assert_eq!(
+        bsp.faces_in_box(&bounds([4.0, 4.0, 0.0], [8.0, 8.0, 4.0])),
+        [0]
+    );
+    assert_eq!(
+        bsp.faces_in_box(&bounds([4.0, 4.0, -4.0], [8.0, 8.0, 0.0])),
+        [0]
+    );
+    assert_eq!(
+        bsp.faces_in_box(&bounds([16.0, 16.0, -4.0], [20.0, 20.0, 4.0])),
+        [0]
+    );
+
+    assert!(bsp
+        .faces_in_box(&bounds([4.0, 4.0, 1.0], [8.0, 8.0, 4.0]))
+        .is_empty());
+    // Crossing the plane of the face next to it
+    assert!(bsp
+        .faces_in_box(&bounds([-8.0, 4.0, -4.0], [-1.0, 8.0, 4.0]))
+        .is_empty());
+}
+
+#[test]
+fn finds_entities_in_radius() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_ENTITIES] = b"{\n\"classname\" \"worldspawn\"\n}\n\
+        {\n\"classname\" \"info_target\"\n\"origin\" \"0 0 32\"\n}\n\
+        {\n\"classname\" \"func_wall\"\n\"model\" \"*1\"\n}\n\
+        {\n\"classname\" \"info_null\"\n}\n\0"
+        .to_vec();
+    let mut model = Vec::new();
+    floats(&mut model, &[64.0, 0.0, 0.0, 80.0, 16.0, 16.0]);
+    model.extend_from_slice(&builder.lumps[LUMP_MODELS][24..]);
+    builder.lumps[LUMP_MODELS].extend(model);
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    let origin = Vec3::new(0.0, 0.0, 0.0);
+    assert!(bsp.entities_in_radius(origin, 16.0).is_empty());
+    assert_eq!(bsp.entities_in_radius(origin, 32.0), [1]);
+    // Measured from the closest point of the wall
+    assert_eq!(bsp.entities_in_radius(origin, 64.0), [1, 2]);
+    assert_eq!(bsp.entities_in_radius(Vec3::new(72.0, 8.0, 8.0), 0.0), [2]);
+}
@@ -0,0 +1,82 @@
+use bsp_rs::*;
+
+use common::{Builder, FORMATS};
+
+mod common;
+
+#[test]
+fn validates_references() {
+    for format in FORMATS {
+        let bsp = read_bsp(&Builder::new(format).build()).unwrap();
+        assert_eq!(bsp.validate(), []);
+    }
+
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    builder.lumps[LUMP_SURFEDGES][12..16].copy_from_slice(&9i32.to_le_bytes());
+    let bsp = read_bsp(&builder.build()).unwrap();
+    assert_eq!(
+        bsp.validate(),
+        [BspIssue {
+            severity: Severity::Error,
+            kind: BspIssueKind::InvalidIndex {
+                lump: LUMP_SURFEDGES,
+                record: 3,
+                target: LUMP_EDGES,
+                index: 9,
+            },
+        }]
+    );
+}
+
+#[test]
+fn validates_limits() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    let message = "a".repeat(MAX_MAP_ENTSTRING);
+    builder.lumps[LUMP_ENTITIES] = format!(
+        "{{\n\"classname\" \"worldspawn\"\n\"message\" \"{}\"\n}}\n\0",
+        message
+    )
+    .into_bytes();
+    let size = builder.lumps[LUMP_ENTITIES].len();
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    assert_eq!(
+        bsp.validate(),
+        [BspIssue {
+            severity: Severity::Warning,
+            kind: BspIssueKind::SizeExceeded {
+                lump: LUMP_ENTITIES,
+                size,
+                limit: MAX_MAP_ENTSTRING,
+            },
+        }]
+    );
+
+    // Monochrome lighting is compared before its expansion to RGB
+    let mut builder = Builder::new(BspFormat::Bsp29);
+    builder.lumps[LUMP_LIGHTING] = vec![0; MAX_MAP_LIGHTING / 2];
+    let bsp = read_bsp(&builder.build()).unwrap();
+    assert_eq!(bsp.validate(), []);
+}
+
+#[test]
+fn validates_visible_leaves() {
+    let mut builder = Builder::new(BspFormat::Bsp30);
+    let offset = builder.lumps[LUMP_MODELS].len() - 12;
+    builder.lumps[LUMP_MODELS][offset..offset + 4].copy_from_slice(&2i32.to_le_bytes());
+    let bsp = read_bsp(&builder.build()).unwrap();
+
+    assert_eq!(
+        bsp.validate(),
+        [BspIssue {
+            severity: Severity::Error,
+            kind: BspIssueKind::InvalidRange {
+                lump: LUMP_MODELS,
+                record: 0,
+                target: LUMP_LEAVES,
+                first: 1,
+                count: 2,
+            },
+        }]
+    );
+}
@@ -0,0 +1,453 @@
+use std::fmt;
+
+use bsp_rs::{TextCursor, TextParseError};
+
+pub use validate::*;
+
+mod validate;
+
+/// Hammer game data, describing the entity classes of a game
+#[derive(Debug, Default)]
+pub struct Fgd {
+    /// Files pulled in with `@include`, which are left to the caller to load and [Fgd::merge]
+    pub includes: Vec<String>,
+    /// Map bounds set with `@mapsize`
+    pub map_size: Option<(i32, i32)>,
+    pub classes: Vec<EntityClass>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassKind {
+    /// `@BaseClass`, only inherited from
+    Base,
+    /// `@PointClass`
+    Point,
+    /// `@SolidClass`, brush entities
+    Solid,
+}
+
+#[derive(Debug)]
+pub struct EntityClass {
+    pub kind: ClassKind,
+    pub name: String,
+    pub description: Option<String>,
+    /// Classes named in `base(...)`
+    pub bases: Vec<String>,
+    /// Editor helpers like `size(...)`, `color(...)` or `studio(...)`, base excluded
+    pub helpers: Vec<ClassHelper>,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug)]
+pub struct ClassHelper {
+    pub name: String,
+    /// Comma separated arguments, the tokens of each joined by spaces
+    pub args: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Property {
+    /// The entity key
+    pub name: String,
+    pub kind: PropertyKind,
+    pub display_name: Option<String>,
+    pub default: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum PropertyKind {
+    String,
+    Integer,
+    Float,
+    /// An `r g b` color with an optional brightness
+    Color255,
+    Choices(Vec<Choice>),
+    Flags(Vec<Flag>),
+    /// Types only relevant to the editor, like `studio`, `sound` or `target_source`
+    Other(String),
+}
+
+#[derive(Debug)]
+pub struct Choice {
+    pub value: String,
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct Flag {
+    /// The flag's value in `spawnflags`, a single bit
+    pub mask: u32,
+    pub name: String,
+    /// Whether the flag is set by default
+    pub default: bool,
+}
+
+impl Fgd {
+    pub fn class(&self, name: &str) -> Option<&EntityClass> {
+        self.classes
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the properties of a class including the ones of its bases,
+    /// bases first and later definitions of a key overriding earlier ones
+    pub fn properties<'a>(&'a self, class: &'a EntityClass) -> Vec<&'a Property> {
+        let mut properties = Vec::new();
+        self.collect_properties(class, &mut properties, &mut Vec::new());
+        properties
+    }
+
+    fn collect_properties<'a>(
+        &'a self,
+        class: &'a EntityClass,
+        properties: &mut Vec<&'a Property>,
+        visited: &mut Vec<&'a str>,
+    ) {
+        if visited.contains(&class.name.as_str()) {
+            return;
+        }
+        visited.push(&class.name);
+
+        for base in &class.bases {
+            if let Some(base) = self.class(base) {
+                self.collect_properties(base, properties, visited);
+            }
+        }
+        for property in &class.properties {
+            properties.retain(|p: &&Property| !p.name.eq_ignore_ascii_case(&property.name));
+            properties.push(property);
+        }
+    }
+
+    /// Adds the classes of another file, like one named in [Fgd::includes]
+    pub fn merge(&mut self, other: Fgd) {
+        for class in other.classes {
+            self.classes
+                .retain(|c| !c.name.eq_ignore_ascii_case(&class.name));
+            self.classes.push(class);
+        }
+        self.includes.extend(other.includes);
+        self.map_size = self.map_size.or(other.map_size);
+    }
+}
+
+/// Syntax error in an FGD file
+pub type FgdParseError = TextParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    /// `@Name`
+    Directive(&'a str),
+    /// An unquoted word or number
+    Word(&'a str),
+    /// A quoted string
+    String(&'a str),
+    Punct(char),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Directive(name) => write!(f, "@{}", name),
+            Token::Word(word) => write!(f, "{}", word),
+            Token::String(string) => write!(f, "\"{}\"", string),
+            Token::Punct(c) => write!(f, "'{}'", c),
+        }
+    }
+}
+
+const PUNCTUATION: &[char] = &['(', ')', '[', ']', ',', ':', '=', '+'];
+
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, usize, usize)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a str) -> Result<Self, FgdParseError> {
+        let mut tokens = Vec::new();
+        let mut cursor = TextCursor::new(data);
+
+        loop {
+            cursor.skip_blank(|_| false);
+            let (line, column) = (cursor.line, cursor.column);
+            let Some((start, c)) = cursor.bump() else {
+                break;
+            };
+
+            let token = if c == '"' {
+                let string = cursor.quoted(start).ok_or_else(|| FgdParseError {
+                    line,
+                    column,
+                    message: "unterminated string".to_string(),
+                })?;
+                Token::String(string)
+            } else if PUNCTUATION.contains(&c) {
+                Token::Punct(c)
+            } else {
+                let word = cursor.word(start, |c| c == '"' || c == '@' || PUNCTUATION.contains(&c));
+                match word.strip_prefix('@') {
+                    Some(name) => Token::Directive(name),
+                    None => Token::Word(word),
+                }
+            };
+            tokens.push((token, line, column));
+        }
+
+        Ok(Self { tokens, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|&(t, _, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, message: impl Into<String>) -> FgdParseError {
+        let last = self.pos.min(self.tokens.len()).saturating_sub(1);
+        let (line, column) = match self.tokens.get(last) {
+            Some(&(_, line, column)) => (line, column),
+            None => (1, 1),
+        };
+        FgdParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn unexpected(&self, token: Option<Token>, expected: &str) -> FgdParseError {
+        match token {
+            Some(token) => self.error(format!("expected {}, found {}", expected, token)),
+            None => self.error(format!("expected {}, found end of file", expected)),
+        }
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        if self.peek() == Some(Token::Punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), FgdParseError> {
+        match self.next() {
+            Some(Token::Punct(c)) if c == punct => Ok(()),
+            token => Err(self.unexpected(token, &format!("'{}'", punct))),
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str, FgdParseError> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            token => Err(self.unexpected(token, "a name")),
+        }
+    }
+
+    /// A string, possibly concatenated from several with `+`
+    fn string(&mut self) -> Result<String, FgdParseError> {
+        let mut string = match self.next() {
+            Some(Token::String(s)) => s.to_string(),
+            token => return Err(self.unexpected(token, "a string")),
+        };
+        while self.eat('+') {
+            match self.next() {
+                Some(Token::String(s)) => string.push_str(s),
+                token => return Err(self.unexpected(token, "a string")),
+            }
+        }
+        Ok(string)
+    }
+
+    /// A string or a number, `None` when the value is left empty
+    fn value(&mut self) -> Result<Option<String>, FgdParseError> {
+        match self.peek() {
+            Some(Token::String(_)) => self.string().map(Some),
+            Some(Token::Word(word)) => {
+                self.pos += 1;
+                Ok(Some(word.to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse(&mut self) -> Result<Fgd, FgdParseError> {
+        let mut fgd = Fgd::default();
+
+        while let Some(token) = self.next() {
+            let Token::Directive(directive) = token else {
+                return Err(self.unexpected(Some(token), "a '@' directive"));
+            };
+
+            match directive.to_ascii_lowercase().as_str() {
+                "include" => fgd.includes.push(self.string()?),
+                "mapsize" => {
+                    self.expect('(')?;
+                    let min = self.number()?;
+                    self.expect(',')?;
+                    let max = self.number()?;
+                    self.expect(')')?;
+                    fgd.map_size = Some((min, max));
+                }
+                "baseclass" => fgd.classes.push(self.class(ClassKind::Base)?),
+                "solidclass" => fgd.classes.push(self.class(ClassKind::Solid)?),
+                name if name.ends_with("class") => fgd.classes.push(self.class(ClassKind::Point)?),
+                _ => return Err(self.error(format!("unknown directive @{}", directive))),
+            }
+        }
+
+        Ok(fgd)
+    }
+
+    fn number(&mut self) -> Result<i32, FgdParseError> {
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| self.error(format!("expected a number, found {}", word)))
+    }
+
+    fn class(&mut self, kind: ClassKind) -> Result<EntityClass, FgdParseError> {
+        let mut bases = Vec::new();
+        let mut helpers = Vec::new();
+
+        while let Some(Token::Word(name)) = self.peek() {
+            self.pos += 1;
+            self.expect('(')?;
+
+            let mut args = Vec::new();
+            let mut arg = Vec::new();
+            loop {
+                match self.next() {
+                    Some(Token::Punct(')')) => break,
+                    Some(Token::Punct(',')) => args.push(std::mem::take(&mut arg).join(" ")),
+                    Some(Token::Word(word) | Token::String(word)) => arg.push(word),
+                    token => return Err(self.unexpected(token, "a helper argument")),
+                }
+            }
+            if !arg.is_empty() {
+                args.push(arg.join(" "));
+            }
+
+            if name.eq_ignore_ascii_case("base") {
+                bases.extend(args);
+            } else {
+                helpers.push(ClassHelper {
+                    name: name.to_string(),
+                    args,
+                });
+            }
+        }
+
+        self.expect('=')?;
+        let name = self.word()?.to_string();
+        let description = if self.eat(':') {
+            Some(self.string()?)
+        } else {
+            None
+        };
+
+        let mut properties = Vec::new();
+        self.expect('[')?;
+        while !self.eat(']') {
+            properties.push(self.property()?);
+        }
+
+        Ok(EntityClass {
+            kind,
+            name,
+            description,
+            bases,
+            helpers,
+            properties,
+        })
+    }
+
+    fn property(&mut self) -> Result<Property, FgdParseError> {
+        let name = self.word()?.to_string();
+        self.expect('(')?;
+        let kind = self.word()?.to_ascii_lowercase();
+        self.expect(')')?;
+
+        // Source style modifiers
+        while let Some(Token::Word(word)) = self.peek() {
+            if !matches!(word, "readonly" | "report") {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        let mut fields = [None, None, None];
+        for field in &mut fields {
+            if !self.eat(':') {
+                break;
+            }
+            *field = self.value()?;
+        }
+        let [display_name, default, description] = fields;
+
+        let kind = match kind.as_str() {
+            "string" => PropertyKind::String,
+            "integer" => PropertyKind::Integer,
+            "float" => PropertyKind::Float,
+            "color255" => PropertyKind::Color255,
+            "choices" => {
+                self.expect('=')?;
+                self.expect('[')?;
+                let mut choices = Vec::new();
+                while !self.eat(']') {
+                    let value = self
+                        .value()?
+                        .ok_or_else(|| self.unexpected(self.peek(), "a choice value"))?;
+                    self.expect(':')?;
+                    let name = self.string()?;
+                    choices.push(Choice { value, name });
+                }
+                PropertyKind::Choices(choices)
+            }
+            "flags" => {
+                self.expect('=')?;
+                self.expect('[')?;
+                let mut flags = Vec::new();
+                while !self.eat(']') {
+                    let mask = self.word()?;
+                    let mask = mask
+                        .parse()
+                        .map_err(|_| self.error(format!("invalid flag {}", mask)))?;
+                    self.expect(':')?;
+                    let name = self.string()?;
+                    let default = if self.eat(':') {
+                        self.word()? != "0"
+                    } else {
+                        false
+                    };
+                    flags.push(Flag {
+                        mask,
+                        name,
+                        default,
+                    });
+                }
+                PropertyKind::Flags(flags)
+            }
+            _ => PropertyKind::Other(kind),
+        };
+
+        Ok(Property {
+            name,
+            kind,
+            display_name,
+            default,
+            description,
+        })
+    }
+}
+
+/// Parses the contents of an FGD file
+pub fn parse_fgd(data: &str) -> Result<Fgd, FgdParseError> {
+    Parser::new(data)?.parse()
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+use bsp_rs::{Bsp, Entity};
+
+use crate::{ClassKind, EntityClass, Fgd, PropertyKind};
+
+/// `spawnflags` bit keeping any entity out of deathmatch games, checked by
+/// the engine when spawning entities
+pub const SF_NOTINDEATHMATCH: u32 = 2048;
+
+/// `spawnflags` bits every entity accepts without its class listing them
+pub const ENGINE_SPAWNFLAGS: u32 = SF_NOTINDEATHMATCH;
+
+/// A problem found while checking entities against an [Fgd]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FgdIssue {
+    /// Index of the entity
+    pub entity: usize,
+    pub kind: FgdIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FgdIssueKind {
+    MissingClassname,
+    /// The classname isn't defined, or only as a `@BaseClass`
+    UnknownClass(String),
+    /// A key every entity of the class needs, see [EntityClass::required_keys]
+    MissingKey(String),
+    /// A `choices` value that isn't one of the choices
+    InvalidChoice {
+        key: String,
+        value: String,
+    },
+    /// A value that doesn't parse as the property's type
+    InvalidValue {
+        key: String,
+        value: String,
+    },
+    /// `spawnflags` bits without a matching flag, apart from [ENGINE_SPAWNFLAGS]
+    UnknownSpawnflags(u32),
+}
+
+impl fmt::Display for FgdIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entity {}: ", self.entity)?;
+        match &self.kind {
+            FgdIssueKind::MissingClassname => write!(f, "missing classname"),
+            FgdIssueKind::UnknownClass(classname) => write!(f, "unknown class \"{}\"", classname),
+            FgdIssueKind::MissingKey(key) => write!(f, "missing key \"{}\"", key),
+            FgdIssueKind::InvalidChoice { key, value } => {
+                write!(f, "\"{}\" is not a valid choice for \"{}\"", value, key)
+            }
+            FgdIssueKind::InvalidValue { key, value } => {
+                write!(f, "\"{}\" is not a valid value for \"{}\"", value, key)
+            }
+            FgdIssueKind::UnknownSpawnflags(bits) => write!(f, "unknown spawnflags {:#x}", bits),
+        }
+    }
+}
+
+impl EntityClass {
+    /// Keys entities of the class can't do without
+    ///
+    /// Point entities need an `origin`, as well as a `model` when the class
+    /// has a `studio()` or `sprite()` helper showing the model of the key.
+    /// Brush entities need a `model`, except for the world.
+    pub fn required_keys(&self) -> Vec<&'static str> {
+        match self.kind {
+            ClassKind::Base => Vec::new(),
+            ClassKind::Point => {
+                let mut keys = vec!["origin"];
+                if self.helpers.iter().any(|helper| {
+                    helper.args.is_empty()
+                        && ["studio", "sprite"]
+                            .iter()
+                            .any(|name| helper.name.eq_ignore_ascii_case(name))
+                }) {
+                    keys.push("model");
+                }
+                keys
+            }
+            ClassKind::Solid if self.name.eq_ignore_ascii_case("worldspawn") => Vec::new(),
+            ClassKind::Solid => vec!["model"],
+        }
+    }
+}
+
+impl Fgd {
+    /// Checks entities against the classes of this file
+    pub fn validate(&self, entities: &[Entity]) -> Vec<FgdIssue> {
+        let mut issues = Vec::new();
+
+        for (i, entity) in entities.iter().enumerate() {
+            let mut issue = |kind| issues.push(FgdIssue { entity: i, kind });
+
+            let Some(classname) = entity.classname() else {
+                issue(FgdIssueKind::MissingClassname);
+                continue;
+            };
+            let class = match self.class(classname) {
+                Some(class) if class.kind != ClassKind::Base => class,
+                _ => {
+                    issue(FgdIssueKind::UnknownClass(classname.to_string()));
+                    continue;
+                }
+            };
+
+            for key in class.required_keys() {
+                if entity.get(key).is_none() {
+                    issue(FgdIssueKind::MissingKey(key.to_string()));
+                }
+            }
+
+            for property in self.properties(class) {
+                let Some(value) = entity.get(&property.name) else {
+                    continue;
+                };
+
+                let valid = match &property.kind {
+                    PropertyKind::Integer => value.trim().parse::<i32>().is_ok(),
+                    PropertyKind::Float => value.trim().parse::<f32>().is_ok(),
+                    PropertyKind::Color255 => {
+                        let components: Vec<_> = value.split_whitespace().collect();
+                        (3..=4).contains(&components.len())
+                            && components.iter().all(|c| c.parse::<f32>().is_ok())
+                    }
+                    PropertyKind::Choices(choices) => {
+                        if !choices.iter().any(|c| same_value(&c.value, value)) {
+                            issue(FgdIssueKind::InvalidChoice {
+                                key: property.name.clone(),
+                                value: value.to_string(),
+                            });
+                        }
+                        true
+                    }
+                    PropertyKind::Flags(flags) => match value.trim().parse::<u32>() {
+                        Ok(bits) => {
+                            let known = flags
+                                .iter()
+                                .fold(ENGINE_SPAWNFLAGS, |known, flag| known | flag.mask);
+                            if bits & !known != 0 {
+                                issue(FgdIssueKind::UnknownSpawnflags(bits & !known));
+                            }
+                            true
+                        }
+                        Err(_) => false,
+                    },
+                    PropertyKind::String | PropertyKind::Other(_) => true,
+                };
+
+                if !valid {
+                    issue(FgdIssueKind::InvalidValue {
+                        key: property.name.clone(),
+                        value: value.to_string(),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Checks the entities of a map, see [Fgd::validate]
+    pub fn validate_bsp(&self, bsp: &Bsp) -> Vec<FgdIssue> {
+        self.validate(&bsp.entities)
+    }
+}
+
+/// Compares choice values numerically when both are numbers, so `"1"` matches `"1.0"`
+fn same_value(a: &str, b: &str) -> bool {
+    match (a.trim().parse::<f32>(), b.trim().parse::<f32>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.trim().eq_ignore_ascii_case(b.trim()),
+    }
+}
@@ -0,0 +1,219 @@
+use bsp_rs::{parse_entities, Entity};
+use fgd_rs::*;
+
+const FGD: &str = r#"
+@mapsize(-4096, 4096)
+
+@BaseClass = Targetname [ targetname(target_source) : "Name" ]
+@BaseClass base(Targetname) = Appearance
+[
+    rendermode(choices) : "Render Mode" : 0 =
+    [
+        0: "Normal"
+        1: "Color"
+    ]
+    renderamt(integer) : "FX Amount (1 - 255)"
+]
+
+@SolidClass = worldspawn : "World entity" [ skyname(string) : "Sky name" ]
+
+@SolidClass base(Appearance) = func_wall : "Wall" + " that " + "does nothing"
+[
+    renderamt(integer) : "Amount" : 255
+    spawnflags(flags) =
+    [
+        1 : "Start off" : 0
+        4 : "Not in deathmatch" : 1
+    ]
+]
+
+@PointClass base(Targetname) size(-16 -16 -16, 16 16 16) color(255 255 0) = info_target : "Beam Target" []
+@PointClass studio() = cycler : "Monster cycler"
+[
+    scale(float) : "Scale"
+    _light(color255) : "Brightness"
+]
+"#;
+
+fn fgd() -> Fgd {
+    parse_fgd(FGD).unwrap()
+}
+
+fn entities(data: &str) -> Vec<Entity> {
+    parse_entities(data).unwrap()
+}
+
+#[test]
+fn parses_classes() {
+    let fgd = fgd();
+    assert_eq!(fgd.map_size, Some((-4096, 4096)));
+    assert_eq!(fgd.classes.len(), 6);
+
+    let target = fgd.class("info_target").unwrap();
+    assert_eq!(target.kind, ClassKind::Point);
+    assert_eq!(target.bases, ["Targetname"]);
+    let helpers: Vec<_> = target
+        .helpers
+        .iter()
+        .map(|h| (h.name.as_str(), h.args.clone()))
+        .collect();
+    assert_eq!(
+        helpers,
+        [
+            (
+                "size",
+                vec!["-16 -16 -16".to_string(), "16 16 16".to_string()]
+            ),
+            ("color", vec!["255 255 0".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn joins_strings() {
+    let fgd = fgd();
+
+    assert_eq!(
+        fgd.class("func_wall").unwrap().description.as_deref(),
+        Some("Wall that does nothing")
+    );
+}
+
+#[test]
+fn inherits_base_properties() {
+    let fgd = fgd();
+    let wall = fgd.class("func_wall").unwrap();
+
+    let properties = fgd.properties(wall);
+    let names: Vec<_> = properties.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["targetname", "rendermode", "renderamt", "spawnflags"]
+    );
+
+    // func_wall overrides the renderamt of Appearance
+    let renderamt = properties[2];
+    assert_eq!(renderamt.display_name.as_deref(), Some("Amount"));
+    assert_eq!(renderamt.default.as_deref(), Some("255"));
+}
+
+#[test]
+fn parses_choices() {
+    let fgd = fgd();
+    let appearance = fgd.class("Appearance").unwrap();
+
+    let rendermode = &appearance.properties[0];
+    assert_eq!(rendermode.default.as_deref(), Some("0"));
+    let PropertyKind::Choices(choices) = &rendermode.kind else {
+        panic!("{:?}", rendermode.kind);
+    };
+    let choices: Vec<_> = choices
+        .iter()
+        .map(|c| (c.value.as_str(), c.name.as_str()))
+        .collect();
+    assert_eq!(choices, [("0", "Normal"), ("1", "Color")]);
+}
+
+#[test]
+fn parses_flags() {
+    let fgd = fgd();
+    let wall = fgd.class("func_wall").unwrap();
+
+    let PropertyKind::Flags(flags) = &wall.properties[1].kind else {
+        panic!("{:?}", wall.properties[1].kind);
+    };
+    let flags: Vec<_> = flags
+        .iter()
+        .map(|f| (f.mask, f.name.as_str(), f.default))
+        .collect();
+    assert_eq!(
+        flags,
+        [(1, "Start off", false), (4, "Not in deathmatch", true)]
+    );
+}
+
+#[test]
+fn reports_parse_errors() {
+    let error = parse_fgd("@PointClass = info_target : \"Target\n[]").unwrap_err();
+    assert_eq!((error.line, error.column), (1, 29));
+}
+
+#[test]
+fn accepts_valid_entities() {
+    let fgd = fgd();
+    let entities = entities(
+        r#"
+{ "classname" "worldspawn" "skyname" "desert" }
+{ "classname" "func_wall" "model" "*1" "rendermode" "1.0" "spawnflags" "2053" }
+{ "classname" "info_target" "origin" "0 0 0" "targetname" "a" }
+{ "classname" "cycler" "origin" "0 0 0" "model" "models/g.mdl" "_light" "255 255 255 200" }
+"#,
+    );
+
+    assert_eq!(fgd.validate(&entities), []);
+}
+
+#[test]
+fn reports_every_issue_kind() {
+    let fgd = fgd();
+    let entities = entities(
+        r#"
+{ "origin" "0 0 0" }
+{ "classname" "monster_zombie" }
+{ "classname" "Targetname" }
+{ "classname" "func_wall" }
+{ "classname" "cycler" "origin" "0 0 0" }
+{ "classname" "func_wall" "model" "*1" "rendermode" "7" }
+{ "classname" "cycler" "origin" "0 0 0" "model" "a.mdl" "scale" "big" "_light" "255 0" }
+{ "classname" "func_wall" "model" "*2" "spawnflags" "2059" }
+"#,
+    );
+
+    assert_eq!(
+        fgd.validate(&entities),
+        [
+            (0, FgdIssueKind::MissingClassname),
+            (1, FgdIssueKind::UnknownClass("monster_zombie".to_string())),
+            (2, FgdIssueKind::UnknownClass("Targetname".to_string())),
+            (3, FgdIssueKind::MissingKey("model".to_string())),
+            (4, FgdIssueKind::MissingKey("model".to_string())),
+            (
+                5,
+                FgdIssueKind::InvalidChoice {
+                    key: "rendermode".to_string(),
+                    value: "7".to_string(),
+                },
+            ),
+            (
+                6,
+                FgdIssueKind::InvalidValue {
+                    key: "scale".to_string(),
+                    value: "big".to_string(),
+                },
+            ),
+            (
+                6,
+                FgdIssueKind::InvalidValue {
+                    key: "_light".to_string(),
+                    value: "255 0".to_string(),
+                },
+            ),
+            (7, FgdIssueKind::UnknownSpawnflags(10)),
+        ]
+        .map(|(entity, kind)| FgdIssue { entity, kind })
+    );
+}
+
+#[test]
+fn requires_origins_of_point_entities() {
+    let fgd = fgd();
+    let entities = entities(r#"{ "classname" "info_target" }"#);
+
+    assert_eq!(
+        fgd.validate(&entities),
+        [FgdIssue {
+            entity: 0,
+            kind: FgdIssueKind::MissingKey("origin".to_string()),
+        }]
+    );
+}
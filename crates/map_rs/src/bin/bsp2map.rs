@@ -0,0 +1,39 @@
+//! Decompiles a map to a Valve 220 `.map` file
+//!
+//! ```text
+//! bsp2map <map.bsp> [map.map]
+//! ```
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use bsp_rs::read_bsp;
+use map_rs::{decompile, write_map};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (bsp_path, map_path) = match args.as_slice() {
+        [bsp] => (PathBuf::from(bsp), PathBuf::from(bsp).with_extension("map")),
+        [bsp, map] => (PathBuf::from(bsp), PathBuf::from(map)),
+        _ => {
+            eprintln!("usage: bsp2map <map.bsp> [map.map]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = fs::read(&bsp_path)
+        .map_err(|err| format!("couldn't read {}: {}", bsp_path.display(), err))
+        .and_then(|bytes| read_bsp(&bytes).map_err(|err| err.to_string()))
+        .and_then(|bsp| write_map(&decompile(&bsp)).map_err(|err| err.to_string()))
+        .and_then(|data| {
+            fs::write(&map_path, data)
+                .map_err(|err| format!("couldn't write {}: {}", map_path.display(), err))
+        });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
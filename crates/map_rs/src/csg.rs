@@ -0,0 +1,227 @@
+use bsp_rs::{BoundBox, Vec3};
+
+use crate::Brush;
+
+/// Half the size of the winding [Winding::for_plane] starts from, larger
+/// than any map
+pub const BOGUS_RANGE: f32 = 16384.0;
+/// Distance under which a point is considered on a plane
+pub const ON_EPSILON: f32 = 0.1;
+/// Area under which a clipped winding is considered gone
+pub const MIN_WINDING_AREA: f32 = 0.1;
+
+/// A convex polygon
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Winding {
+    pub points: Vec<Vec3>,
+}
+
+impl Winding {
+    /// A huge square on a plane, like `BaseWindingForPlane`
+    pub fn for_plane(normal: Vec3, dist: f32) -> Self {
+        let up = if normal.z.abs() > normal.x.abs().max(normal.y.abs()) {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 0.0, 1.0)
+        };
+        let up = (up - normal * up.dot(normal)).normalize() * BOGUS_RANGE;
+        let right = up.cross(normal);
+        let origin = normal * dist;
+
+        Self {
+            points: vec![
+                origin - right + up,
+                origin + right + up,
+                origin + right - up,
+                origin - right - up,
+            ],
+        }
+    }
+
+    /// Keeps the part of the winding behind a plane, like `ClipWinding`
+    ///
+    /// Points within `epsilon` of the plane are kept as they are.
+    pub fn clip(&self, normal: Vec3, dist: f32, epsilon: f32) -> Self {
+        let dists: Vec<f32> = self.points.iter().map(|&p| normal.dot(p) - dist).collect();
+        if dists.iter().all(|&d| d <= epsilon) {
+            return self.clone();
+        }
+        if dists.iter().all(|&d| d >= -epsilon) {
+            return Self::default();
+        }
+
+        let mut points = Vec::new();
+        for i in 0..self.points.len() {
+            let j = (i + 1) % self.points.len();
+            let (p1, d1) = (self.points[i], dists[i]);
+            let (p2, d2) = (self.points[j], dists[j]);
+
+            if d1.abs() <= epsilon {
+                points.push(p1);
+                continue;
+            }
+            if d1 < 0.0 {
+                points.push(p1);
+            }
+            if d2.abs() <= epsilon || (d1 < 0.0) == (d2 < 0.0) {
+                continue;
+            }
+
+            points.push(p1 + (p2 - p1) * (d1 / (d1 - d2)));
+        }
+
+        Self { points }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.len() < 3
+    }
+
+    pub fn area(&self) -> f32 {
+        let Some(&first) = self.points.first() else {
+            return 0.0;
+        };
+
+        self.points
+            .windows(2)
+            .skip(1)
+            .map(|pair| (pair[0] - first).cross(pair[1] - first).length() * 0.5)
+            .sum()
+    }
+
+    /// Average of the points
+    pub fn center(&self) -> Vec3 {
+        let sum = self
+            .points
+            .iter()
+            .fold(Vec3::new(0.0, 0.0, 0.0), |sum, &p| sum + p);
+        sum * (1.0 / self.points.len().max(1) as f32)
+    }
+
+    pub fn bounds(&self) -> Option<BoundBox> {
+        bounds(self.points.iter().copied())
+    }
+}
+
+/// A bounding face of a [Polyhedron]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolyhedronFace {
+    /// Index of the half-space the face lies on
+    pub plane: usize,
+    pub normal: Vec3,
+    pub dist: f32,
+    pub winding: Winding,
+}
+
+/// A convex volume built from half-spaces
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polyhedron {
+    /// Faces of the half-spaces touching the volume, redundant and
+    /// duplicate ones being left out
+    pub faces: Vec<PolyhedronFace>,
+}
+
+impl Polyhedron {
+    /// Intersects the half-spaces behind planes given as normal and distance
+    pub fn from_planes(planes: &[(Vec3, f32)]) -> Self {
+        let mut faces = Vec::new();
+
+        for (i, &(normal, dist)) in planes.iter().enumerate() {
+            if planes[..i]
+                .iter()
+                .any(|&other| same_plane((normal, dist), other))
+            {
+                continue;
+            }
+
+            let mut winding = Winding::for_plane(normal, dist);
+            for (j, &other) in planes.iter().enumerate() {
+                if i == j || same_plane((normal, dist), other) {
+                    continue;
+                }
+                winding = winding.clip(other.0, other.1, ON_EPSILON);
+                if winding.is_empty() {
+                    break;
+                }
+            }
+            if winding.area() < MIN_WINDING_AREA {
+                continue;
+            }
+
+            faces.push(PolyhedronFace {
+                plane: i,
+                normal,
+                dist,
+                winding,
+            });
+        }
+
+        Self { faces }
+    }
+
+    /// Whether the half-spaces enclose a volume
+    ///
+    /// Faces of open volumes run to the edge of the winding they were cut
+    /// from, past half of [BOGUS_RANGE].
+    pub fn is_closed(&self) -> bool {
+        let limit = BOGUS_RANGE / 2.0;
+        self.faces.len() >= 4
+            && self.bounds().is_some_and(|bounds| {
+                [bounds.min, bounds.max]
+                    .iter()
+                    .all(|p| p.x.abs().max(p.y.abs()).max(p.z.abs()) < limit)
+            })
+    }
+
+    pub fn bounds(&self) -> Option<BoundBox> {
+        bounds(
+            self.faces
+                .iter()
+                .flat_map(|face| face.winding.points.iter().copied()),
+        )
+    }
+
+    /// Whether a point is inside, or within [ON_EPSILON] of a face
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.is_closed()
+            && self
+                .faces
+                .iter()
+                .all(|face| face.normal.dot(point) - face.dist <= ON_EPSILON)
+    }
+}
+
+impl Brush {
+    /// Computes the geometry of the brush, [PolyhedronFace::plane] being the
+    /// index of the face
+    pub fn polyhedron(&self) -> Polyhedron {
+        let planes: Vec<_> = self.faces.iter().map(|face| face.plane()).collect();
+        Polyhedron::from_planes(&planes)
+    }
+}
+
+fn same_plane(a: (Vec3, f32), b: (Vec3, f32)) -> bool {
+    a.0.dot(b.0) > 0.9999 && (a.1 - b.1).abs() < ON_EPSILON
+}
+
+fn bounds(mut points: impl Iterator<Item = Vec3>) -> Option<BoundBox> {
+    let first = points.next()?;
+    Some(points.fold(
+        BoundBox {
+            min: first,
+            max: first,
+        },
+        |bounds, p| BoundBox {
+            min: Vec3::new(
+                bounds.min.x.min(p.x),
+                bounds.min.y.min(p.y),
+                bounds.min.z.min(p.z),
+            ),
+            max: Vec3::new(
+                bounds.max.x.max(p.x),
+                bounds.max.y.max(p.y),
+                bounds.max.z.max(p.z),
+            ),
+        },
+    ))
+}
@@ -0,0 +1,291 @@
+use bsp_rs::{Bsp, LeafContent, Vec3};
+
+use crate::{default_axes, Brush, BrushFace, Map, MapEntity, Polyhedron, TextureAxis, Winding};
+
+/// Texture of brush faces no surface of the map lies on, which ZHLT strips
+/// when compiling
+pub const HIDDEN_TEXTURE: &str = "NULL";
+/// Texture of the brush marking a brush entity's origin
+pub const ORIGIN_TEXTURE: &str = "ORIGIN";
+
+/// Reconstructs the source of a map as convex brushes
+///
+/// Brushes are the solid and liquid leaves of each model's BSP tree, so they
+/// are split along every plane of the tree rather than being the original
+/// ones. Faces take the texture and alignment of the surface lying on them,
+/// or [HIDDEN_TEXTURE]. Clip brushes only exist in the clip hulls and are
+/// not recovered.
+pub fn decompile(bsp: &Bsp) -> Map {
+    let mut map = Map::default();
+
+    for (i, entity) in bsp.entities.iter().enumerate() {
+        let mut entity = entity.clone();
+        let model = if i == 0 {
+            Some(0)
+        } else {
+            entity
+                .get("model")
+                .and_then(|model| model.strip_prefix('*'))
+                .and_then(|index| index.parse::<usize>().ok())
+        };
+
+        let mut brushes = Vec::new();
+        if let Some(model) = model.filter(|&model| model < bsp.models.len()) {
+            // Brushes of entities rotating around an origin are stored around
+            // the world origin
+            let origin = match entity.get_vec3("origin") {
+                Some(origin) if i != 0 => {
+                    entity.remove("origin");
+                    brushes.push(origin_brush(origin));
+                    origin
+                }
+                _ => Vec3::new(0.0, 0.0, 0.0),
+            };
+
+            if i != 0 {
+                entity.remove("model");
+            }
+            brushes.splice(0..0, model_brushes(bsp, model, origin));
+        }
+
+        if i == 0 {
+            entity.set("mapversion", "220");
+        }
+        map.entities.push(MapEntity { entity, brushes });
+    }
+
+    map
+}
+
+/// A bounding plane of a brush
+#[derive(Debug, Clone, Copy)]
+struct Side {
+    normal: Vec3,
+    dist: f32,
+    /// Node the plane was taken from, whose faces texture it
+    node: Option<usize>,
+}
+
+fn model_brushes(bsp: &Bsp, model: usize, origin: Vec3) -> Vec<Brush> {
+    let model = &bsp.models[model];
+    let bounds = &model.bounding_box;
+
+    // Close off the cells at the edges of the model
+    let mut sides = Vec::new();
+    for (axis, min, max) in [
+        (Vec3::new(1.0, 0.0, 0.0), bounds.min.x, bounds.max.x),
+        (Vec3::new(0.0, 1.0, 0.0), bounds.min.y, bounds.max.y),
+        (Vec3::new(0.0, 0.0, 1.0), bounds.min.z, bounds.max.z),
+    ] {
+        sides.push(Side {
+            normal: axis,
+            dist: max,
+            node: None,
+        });
+        sides.push(Side {
+            normal: -axis,
+            dist: -min,
+            node: None,
+        });
+    }
+
+    let mut brushes = Vec::new();
+    walk(
+        bsp,
+        model.head_nodes[0],
+        0,
+        &mut sides,
+        origin,
+        &mut brushes,
+    );
+    brushes
+}
+
+/// Walks the tree down to the leaves, keeping the planes bounding the
+/// current cell in `sides`
+///
+/// A path through a valid tree visits each node at most once, so paths
+/// deeper than the node count are cycles in a corrupt map and are cut off.
+fn walk(
+    bsp: &Bsp,
+    child: i32,
+    depth: usize,
+    sides: &mut Vec<Side>,
+    origin: Vec3,
+    brushes: &mut Vec<Brush>,
+) {
+    if depth > bsp.nodes.len() {
+        return;
+    }
+
+    if child < 0 {
+        let Some(leaf) = bsp.leaves.get((-1 - child) as usize) else {
+            return;
+        };
+        if !matches!(leaf.contents, LeafContent::Empty) {
+            brushes.extend(build_brush(bsp, sides, origin));
+        }
+        return;
+    }
+
+    let Some(node) = bsp.nodes.get(child as usize) else {
+        return;
+    };
+    let Some(plane) = bsp.planes.get(node.plane_index as usize) else {
+        return;
+    };
+
+    // The front child is the cell in front of the plane, bounded by its back
+    sides.push(Side {
+        normal: -plane.normal,
+        dist: -plane.dist,
+        node: Some(child as usize),
+    });
+    walk(bsp, node.children[0], depth + 1, sides, origin, brushes);
+    sides.pop();
+
+    sides.push(Side {
+        normal: plane.normal,
+        dist: plane.dist,
+        node: Some(child as usize),
+    });
+    walk(bsp, node.children[1], depth + 1, sides, origin, brushes);
+    sides.pop();
+}
+
+fn build_brush(bsp: &Bsp, sides: &[Side], origin: Vec3) -> Option<Brush> {
+    let planes: Vec<_> = sides.iter().map(|side| (side.normal, side.dist)).collect();
+    let polyhedron = Polyhedron::from_planes(&planes);
+    if !polyhedron.is_closed() {
+        return None;
+    }
+
+    let faces = polyhedron
+        .faces
+        .iter()
+        .map(|face| brush_face(bsp, &sides[face.plane], &face.winding, origin))
+        .collect();
+    Some(Brush { faces })
+}
+
+/// Turns a side into a face, texturing it with the closest surface facing
+/// the same way on its node
+fn brush_face(bsp: &Bsp, side: &Side, winding: &Winding, origin: Vec3) -> BrushFace {
+    let center = winding.center();
+
+    let surface = side.node.and_then(|node| {
+        let node = bsp.nodes.get(node)?;
+        let first = node.first_face as usize;
+        bsp.faces
+            .get(first..first + node.num_faces as usize)?
+            .iter()
+            .filter(|face| bsp.face_normal(face).dot(side.normal) > 0.99)
+            .map(|face| {
+                let count = face.edges.max(1) as f32;
+                let face_center = bsp
+                    .face_vertices(face)
+                    .fold(Vec3::new(0.0, 0.0, 0.0), |sum, p| sum + p)
+                    * (1.0 / count);
+                (face, (face_center - center).length())
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(face, _)| face)
+    });
+    // Texture axes without a length have no scale to recover
+    let texture_info = surface
+        .and_then(|face| bsp.texture_infos.get(face.texture_info as usize))
+        .filter(|texture_info| texture_info.s.length() > 0.0 && texture_info.t.length() > 0.0);
+
+    let (texture, u, v, scale) = match texture_info {
+        Some(texture_info) => {
+            let texture = bsp
+                .textures
+                .get(texture_info.miptex as usize)
+                .map_or(HIDDEN_TEXTURE.to_string(), |t| t.name.clone());
+            let u_scale = 1.0 / texture_info.s.length();
+            let v_scale = 1.0 / texture_info.t.length();
+            (
+                texture,
+                TextureAxis {
+                    axis: texture_info.s.normalize(),
+                    shift: texture_info.s_shift - texture_info.s.dot(origin),
+                },
+                TextureAxis {
+                    axis: texture_info.t.normalize(),
+                    shift: texture_info.t_shift - texture_info.t.dot(origin),
+                },
+                [u_scale, v_scale],
+            )
+        }
+        None => {
+            let (u, v) = default_axes(side.normal);
+            (
+                HIDDEN_TEXTURE.to_string(),
+                TextureAxis {
+                    axis: u,
+                    shift: 0.0,
+                },
+                TextureAxis {
+                    axis: v,
+                    shift: 0.0,
+                },
+                [1.0, 1.0],
+            )
+        }
+    };
+
+    BrushFace {
+        points: plane_points(
+            side.normal,
+            side.dist + side.normal.dot(origin),
+            center + origin,
+        ),
+        texture,
+        u,
+        v,
+        rotation: 0.0,
+        scale,
+    }
+}
+
+/// A 16 unit cube around a brush entity's origin
+fn origin_brush(origin: Vec3) -> Brush {
+    let mut faces = Vec::new();
+    for axis in [
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    ] {
+        for normal in [axis, -axis] {
+            let (u, v) = default_axes(normal);
+            faces.push(BrushFace {
+                points: plane_points(normal, normal.dot(origin) + 8.0, origin + normal * 8.0),
+                texture: ORIGIN_TEXTURE.to_string(),
+                u: TextureAxis {
+                    axis: u,
+                    shift: 0.0,
+                },
+                v: TextureAxis {
+                    axis: v,
+                    shift: 0.0,
+                },
+                rotation: 0.0,
+                scale: [1.0, 1.0],
+            });
+        }
+    }
+    Brush { faces }
+}
+
+/// Picks three points on a plane around `center`, rounded to whole units
+/// where the plane allows it
+fn plane_points(normal: Vec3, dist: f32, center: Vec3) -> [Vec3; 3] {
+    let rounded = Vec3::new(center.x.round(), center.y.round(), center.z.round());
+    let p1 = rounded - normal * (normal.dot(rounded) - dist);
+
+    let (u, _) = default_axes(normal);
+    let a = (u - normal * u.dot(normal)).normalize();
+    let b = normal.cross(a);
+
+    [p1 + a * 64.0, p1, p1 + b * 64.0]
+}
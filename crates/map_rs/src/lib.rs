@@ -0,0 +1,432 @@
+use std::fmt::Write;
+
+use bsp_rs::{
+    format_f32, write_entity_keys, Entity, EntityWriteError, TextCursor, TextParseError, Vec3,
+};
+
+pub use csg::*;
+pub use decompile::*;
+
+mod csg;
+mod decompile;
+
+/// A map source file, a list of entities with their brushes
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    /// How texture alignment is written
+    pub format: MapFormat,
+    pub entities: Vec<MapEntity>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MapFormat {
+    /// Shift, rotation and scale relative to the axial plane closest to
+    /// each face
+    Quake,
+    /// Explicit texture axes, flagged with `"mapversion" "220"`
+    #[default]
+    Valve220,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MapEntity {
+    pub entity: Entity,
+    /// Brushes of a brush entity, empty for point entities
+    pub brushes: Vec<Brush>,
+}
+
+/// A convex volume, the intersection of the half-spaces behind its faces
+#[derive(Debug, Clone, Default)]
+pub struct Brush {
+    pub faces: Vec<BrushFace>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BrushFace {
+    /// Three points on the plane, clockwise when seen from the front
+    pub points: [Vec3; 3],
+    pub texture: String,
+    pub u: TextureAxis,
+    pub v: TextureAxis,
+    /// Rotation in degrees, only informative as it is baked into the axes
+    pub rotation: f32,
+    pub scale: [f32; 2],
+}
+
+/// A texture axis in world space along with its shift in texels
+#[derive(Debug, Clone, Copy)]
+pub struct TextureAxis {
+    pub axis: Vec3,
+    pub shift: f32,
+}
+
+impl BrushFace {
+    /// Creates a face aligned like the Quake format does, the axes being
+    /// those of the closest axial plane rotated by `rotation` degrees
+    pub fn with_quake_alignment(
+        points: [Vec3; 3],
+        texture: &str,
+        shift: [f32; 2],
+        rotation: f32,
+        scale: [f32; 2],
+    ) -> Self {
+        let mut face = Self {
+            points,
+            texture: texture.to_string(),
+            u: TextureAxis {
+                axis: Vec3::new(0.0, 0.0, 0.0),
+                shift: shift[0],
+            },
+            v: TextureAxis {
+                axis: Vec3::new(0.0, 0.0, 0.0),
+                shift: shift[1],
+            },
+            rotation,
+            scale: scale.map(|s| if s == 0.0 { 1.0 } else { s }),
+        };
+
+        // Rotate around the axis the base axes don't use, like qbsp's
+        // `TexinfoForBrushTexture`
+        let (u, v) = default_axes(face.plane().0);
+        let (sin, cos) = match rotation {
+            0.0 => (0.0, 1.0),
+            90.0 => (1.0, 0.0),
+            180.0 => (0.0, -1.0),
+            270.0 => (-1.0, 0.0),
+            r => r.to_radians().sin_cos(),
+        };
+        let component = |axis: Vec3| {
+            if axis.x != 0.0 {
+                0
+            } else if axis.y != 0.0 {
+                1
+            } else {
+                2
+            }
+        };
+        let (sv, tv) = (component(u), component(v));
+
+        for (axis, out) in [(u, &mut face.u.axis), (v, &mut face.v.axis)] {
+            let mut values: [f32; 3] = axis.into();
+            let (s, t) = (values[sv], values[tv]);
+            values[sv] = cos * s - sin * t;
+            values[tv] = sin * s + cos * t;
+            *out = Vec3::new(values[0], values[1], values[2]);
+        }
+
+        face
+    }
+
+    /// Returns the outward normal and distance of the face's plane
+    pub fn plane(&self) -> (Vec3, f32) {
+        let [p0, p1, p2] = self.points;
+        let normal = (p0 - p1).cross(p2 - p1).normalize();
+        (normal, normal.dot(p1))
+    }
+}
+
+/// Texture axes of the axial plane closest to a normal, like Quake's
+/// `TextureAxisFromPlane`
+pub(crate) fn default_axes(normal: Vec3) -> (Vec3, Vec3) {
+    const BASE_AXES: [[[f32; 3]; 3]; 6] = [
+        [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+        [[0.0, 0.0, -1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]],
+        [[-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]],
+        [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+        [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+    ];
+    let vec3 = |v: [f32; 3]| Vec3::new(v[0], v[1], v[2]);
+
+    let mut best = 0;
+    let mut best_dot = 0.0;
+    for (i, [axis, ..]) in BASE_AXES.iter().enumerate() {
+        let dot = normal.dot(vec3(*axis));
+        if dot > best_dot {
+            best = i;
+            best_dot = dot;
+        }
+    }
+
+    (vec3(BASE_AXES[best][1]), vec3(BASE_AXES[best][2]))
+}
+
+/// Writes a map in its [MapFormat]
+///
+/// Valve 220 maps should have `"mapversion" "220"` in the world for
+/// compilers to read the texture axes. The Quake format can't hold arbitrary
+/// axes, so faces are written with their shift, rotation and scale only.
+///
+/// Fails when a key or value contains a double quote, like
+/// [write_entities](bsp_rs::write_entities).
+pub fn write_map(map: &Map) -> Result<String, EntityWriteError> {
+    let mut data = String::new();
+
+    for (i, entity) in map.entities.iter().enumerate() {
+        writeln!(data, "// entity {}", i).unwrap();
+        data.push_str("{\n");
+        write_entity_keys(&mut data, i, &entity.entity)?;
+
+        for (j, brush) in entity.brushes.iter().enumerate() {
+            writeln!(data, "// brush {}", j).unwrap();
+            data.push_str("{\n");
+            for face in &brush.faces {
+                for point in face.points {
+                    write!(
+                        data,
+                        "( {} {} {} ) ",
+                        format_f32(point.x),
+                        format_f32(point.y),
+                        format_f32(point.z)
+                    )
+                    .unwrap();
+                }
+                data.push_str(&face.texture);
+                for axis in [face.u, face.v] {
+                    match map.format {
+                        MapFormat::Quake => write!(data, " {}", format_f32(axis.shift)).unwrap(),
+                        MapFormat::Valve220 => write!(
+                            data,
+                            " [ {} {} {} {} ]",
+                            format_f32(axis.axis.x),
+                            format_f32(axis.axis.y),
+                            format_f32(axis.axis.z),
+                            format_f32(axis.shift)
+                        )
+                        .unwrap(),
+                    }
+                }
+                writeln!(
+                    data,
+                    " {} {} {}",
+                    format_f32(face.rotation),
+                    format_f32(face.scale[0]),
+                    format_f32(face.scale[1])
+                )
+                .unwrap();
+            }
+            data.push_str("}\n");
+        }
+        data.push_str("}\n");
+    }
+
+    Ok(data)
+}
+
+/// Syntax error in a map file
+pub type MapParseError = TextParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    /// An unquoted word, number or punctuation
+    Word(&'a str),
+    /// A quoted string
+    String(&'a str),
+}
+
+/// Splits a map into whitespace separated words and quoted strings
+///
+/// Punctuation is always surrounded by whitespace in map files, and texture
+/// names may start with `{`, so it isn't split off of words.
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, usize, usize)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a str) -> Result<Self, MapParseError> {
+        let mut tokens = Vec::new();
+        let mut cursor = TextCursor::new(data);
+
+        loop {
+            cursor.skip_blank(|_| false);
+            let (line, column) = (cursor.line, cursor.column);
+            let Some((start, c)) = cursor.bump() else {
+                break;
+            };
+
+            let token = if c == '"' {
+                let string = cursor.quoted(start).ok_or_else(|| MapParseError {
+                    line,
+                    column,
+                    message: "unterminated string".to_string(),
+                })?;
+                Token::String(string)
+            } else {
+                Token::Word(cursor.word(start, |_| false))
+            };
+            tokens.push((token, line, column));
+        }
+
+        Ok(Self { tokens, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|&(token, ..)| token)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    /// Builds an error at the last read token, or at the end of the data
+    fn error(&self, message: impl Into<String>) -> MapParseError {
+        let (line, column) = self
+            .tokens
+            .get(
+                self.pos
+                    .saturating_sub(1)
+                    .min(self.tokens.len().saturating_sub(1)),
+            )
+            .map_or((1, 1), |&(_, line, column)| (line, column));
+        MapParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), MapParseError> {
+        match self.next() {
+            Some(Token::Word(word)) if word == expected => Ok(()),
+            Some(Token::Word(found)) | Some(Token::String(found)) => {
+                Err(self.error(format!("expected '{}', found \"{}\"", expected, found)))
+            }
+            None => Err(self.error(format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    fn word(&mut self, what: &str) -> Result<&'a str, MapParseError> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            Some(Token::String(found)) => {
+                Err(self.error(format!("expected {}, found \"{}\"", what, found)))
+            }
+            None => Err(self.error(format!("expected {}, found end of file", what))),
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, MapParseError> {
+        let word = self.word("a number")?;
+        word.parse()
+            .map_err(|_| self.error(format!("expected a number, found \"{}\"", word)))
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, MapParseError> {
+        Ok(Vec3::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    fn entity(&mut self, format: &mut MapFormat) -> Result<MapEntity, MapParseError> {
+        self.expect("{")?;
+        let mut entity = MapEntity::default();
+
+        loop {
+            match self.next() {
+                Some(Token::String(key)) => match self.next() {
+                    Some(Token::String(value)) => entity
+                        .entity
+                        .properties
+                        .push((key.to_string(), value.to_string())),
+                    _ => return Err(self.error(format!("key \"{}\" has no value", key))),
+                },
+                Some(Token::Word("{")) => entity.brushes.push(self.brush(format)?),
+                Some(Token::Word("}")) => return Ok(entity),
+                Some(Token::Word(found)) => {
+                    return Err(self.error(format!("unexpected \"{}\" in entity", found)))
+                }
+                None => return Err(self.error("unexpected end of file in entity")),
+            }
+        }
+    }
+
+    fn brush(&mut self, format: &mut MapFormat) -> Result<Brush, MapParseError> {
+        let mut brush = Brush::default();
+
+        loop {
+            match self.peek() {
+                Some(Token::Word("}")) => {
+                    self.next();
+                    return Ok(brush);
+                }
+                Some(Token::Word("(")) => brush.faces.push(self.face(format)?),
+                _ => {
+                    let found = self.word("'(' or '}'")?;
+                    return Err(self.error(format!("unexpected \"{}\" in brush", found)));
+                }
+            }
+        }
+    }
+
+    fn face(&mut self, format: &mut MapFormat) -> Result<BrushFace, MapParseError> {
+        let mut points = [Vec3::new(0.0, 0.0, 0.0); 3];
+        for point in &mut points {
+            self.expect("(")?;
+            *point = self.vec3()?;
+            self.expect(")")?;
+        }
+        let texture = self.word("a texture name")?;
+
+        let face = if self.peek() == Some(Token::Word("[")) {
+            *format = MapFormat::Valve220;
+            let mut axes = [TextureAxis {
+                axis: Vec3::new(0.0, 0.0, 0.0),
+                shift: 0.0,
+            }; 2];
+            for axis in &mut axes {
+                self.expect("[")?;
+                axis.axis = self.vec3()?;
+                axis.shift = self.number()?;
+                self.expect("]")?;
+            }
+            BrushFace {
+                points,
+                texture: texture.to_string(),
+                u: axes[0],
+                v: axes[1],
+                rotation: self.number()?,
+                scale: [self.number()?, self.number()?],
+            }
+        } else {
+            let shift = [self.number()?, self.number()?];
+            let rotation = self.number()?;
+            let scale = [self.number()?, self.number()?];
+            BrushFace::with_quake_alignment(points, texture, shift, rotation, scale)
+        };
+
+        // Quake 2 style contents, flags and value
+        while let Some(Token::Word(word)) = self.peek() {
+            if word.parse::<f32>().is_err() {
+                break;
+            }
+            self.next();
+        }
+
+        Ok(face)
+    }
+}
+
+/// Parses a map in the Quake or Valve 220 format
+///
+/// The format is [MapFormat::Valve220] when any face has texture axes or
+/// the world has `"mapversion" "220"`.
+pub fn parse_map(data: &str) -> Result<Map, MapParseError> {
+    let mut parser = Parser::new(data)?;
+    let mut map = Map {
+        format: MapFormat::Quake,
+        entities: Vec::new(),
+    };
+
+    while parser.peek().is_some() {
+        let entity = parser.entity(&mut map.format)?;
+        map.entities.push(entity);
+    }
+
+    let world = map.entities.first().map(|world| &world.entity);
+    if world.and_then(|world| world.get("mapversion")) == Some("220") {
+        map.format = MapFormat::Valve220;
+    }
+
+    Ok(map)
+}
@@ -0,0 +1,95 @@
+use bsp_rs::Vec3;
+use map_rs::*;
+
+fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() < 0.1, "{} != {}", a, b);
+}
+
+fn cube(size: f32) -> Vec<(Vec3, f32)> {
+    [
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    ]
+    .into_iter()
+    .flat_map(|axis| [(axis, size), (-axis, size)])
+    .collect()
+}
+
+#[test]
+fn builds_cubes() {
+    let cube = Polyhedron::from_planes(&cube(16.0));
+
+    assert!(cube.is_closed());
+    assert_eq!(cube.faces.len(), 6);
+    for (i, face) in cube.faces.iter().enumerate() {
+        assert_eq!(face.plane, i);
+        assert_eq!(face.winding.points.len(), 4);
+        assert_close(face.winding.area(), 32.0 * 32.0);
+        assert_close(face.normal.dot(face.winding.center()), 16.0);
+    }
+
+    let bounds = cube.bounds().unwrap();
+    assert_eq!(bounds.min, Vec3::new(-16.0, -16.0, -16.0));
+    assert_eq!(bounds.max, Vec3::new(16.0, 16.0, 16.0));
+
+    assert!(cube.contains(Vec3::new(0.0, 0.0, 0.0)));
+    assert!(cube.contains(Vec3::new(16.0, -16.0, 16.05)));
+    assert!(!cube.contains(Vec3::new(0.0, 0.0, 17.0)));
+}
+
+#[test]
+fn builds_wedges() {
+    let slope = Vec3::new(1.0, 0.0, 1.0).normalize();
+    let planes = [
+        (Vec3::new(-1.0, 0.0, 0.0), 0.0),
+        (Vec3::new(0.0, -1.0, 0.0), 0.0),
+        (Vec3::new(0.0, 1.0, 0.0), 32.0),
+        (Vec3::new(0.0, 0.0, -1.0), 0.0),
+        (slope, 32.0 * slope.x),
+        // Redundant and duplicate half-spaces leave no face
+        (Vec3::new(1.0, 0.0, 0.0), 64.0),
+        (Vec3::new(0.0, 1.0, 0.0), 32.0),
+    ];
+    let wedge = Polyhedron::from_planes(&planes);
+
+    assert!(wedge.is_closed());
+    let faces: Vec<_> = wedge
+        .faces
+        .iter()
+        .map(|face| (face.plane, face.winding.points.len()))
+        .collect();
+    assert_eq!(faces, [(0, 4), (1, 3), (2, 3), (3, 4), (4, 4)]);
+
+    let areas: Vec<_> = wedge.faces.iter().map(|f| f.winding.area()).collect();
+    let expected = [1024.0, 512.0, 512.0, 1024.0, 1024.0 * 2f32.sqrt()];
+    for (&area, expected) in areas.iter().zip(expected) {
+        assert_close(area, expected);
+    }
+
+    let bounds = wedge.bounds().unwrap();
+    assert_eq!(bounds.min, Vec3::new(0.0, 0.0, 0.0));
+    assert_eq!(bounds.max, Vec3::new(32.0, 32.0, 32.0));
+
+    assert!(wedge.contains(Vec3::new(8.0, 16.0, 8.0)));
+    assert!(wedge.contains(Vec3::new(16.0, 16.0, 16.0)));
+    assert!(!wedge.contains(Vec3::new(24.0, 16.0, 24.0)));
+    assert!(!wedge.contains(Vec3::new(8.0, -1.0, 8.0)));
+}
+
+#[test]
+fn rejects_open_volumes() {
+    // Missing the top of the cube
+    let open = Polyhedron::from_planes(&cube(16.0)[..5]);
+    assert!(!open.is_closed());
+    assert!(!open.contains(Vec3::new(0.0, 0.0, 0.0)));
+
+    // Half-spaces without a common volume
+    let planes = [
+        (Vec3::new(1.0, 0.0, 0.0), -16.0),
+        (Vec3::new(-1.0, 0.0, 0.0), -16.0),
+    ];
+    let empty = Polyhedron::from_planes(&[&cube(16.0)[..], &planes].concat());
+    assert!(empty.faces.is_empty());
+    assert!(empty.bounds().is_none());
+}
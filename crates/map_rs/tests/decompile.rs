@@ -0,0 +1,190 @@
+use bsp_rs::*;
+use map_rs::*;
+
+const ENTITIES: &str = "{\n\"classname\" \"worldspawn\"\n}\n\
+    {\n\"classname\" \"info_player_start\"\n\"origin\" \"0 0 32\"\n}\n\
+    {\n\"classname\" \"func_wall\"\n\"model\" \"*1\"\n}\n";
+
+/// Lumps of a BSP30 map whose world is a floor below `z = 0` and whose only
+/// brush entity is a box between `x = 48` and `x = 64`
+fn lumps() -> [Vec<u8>; HEADER_LUMPS] {
+    let mut lumps: [Vec<u8>; HEADER_LUMPS] = Default::default();
+
+    lumps[LUMP_ENTITIES] = ENTITIES.bytes().chain([0]).collect();
+
+    for (normal, dist) in [([0.0f32, 0.0, 1.0], 0.0f32), ([1.0, 0.0, 0.0], 64.0)] {
+        let lump = &mut lumps[LUMP_PLANES];
+        normal.iter().for_each(|v| lump.extend(v.to_le_bytes()));
+        lump.extend(dist.to_le_bytes());
+        lump.extend(0u32.to_le_bytes());
+    }
+
+    lumps[LUMP_TEXTURES] = 0u32.to_le_bytes().to_vec();
+
+    // The front of each plane is empty, the back solid
+    for plane in [0u32, 1] {
+        let lump = &mut lumps[LUMP_NODES];
+        lump.extend(plane.to_le_bytes());
+        for value in [-2i16, -1, 0, 0, 0, 0, 0, 0, 0, 0] {
+            lump.extend(value.to_le_bytes());
+        }
+    }
+
+    for contents in [-2i32, -1] {
+        let lump = &mut lumps[LUMP_LEAVES];
+        lump.extend(contents.to_le_bytes());
+        lump.extend((-1i32).to_le_bytes());
+        lump.extend([0; 20]);
+    }
+
+    for (mins, maxs, head_node) in [
+        ([-16.0f32, -16.0, -16.0], [16.0f32, 16.0, 16.0], 0i32),
+        ([48.0, 0.0, 0.0], [80.0, 16.0, 16.0], 1),
+    ] {
+        let lump = &mut lumps[LUMP_MODELS];
+        for value in mins.iter().chain(&maxs).chain(&[0.0; 3]) {
+            lump.extend(value.to_le_bytes());
+        }
+        for value in [head_node, -1, -1, -1, 1, 0, 0] {
+            lump.extend(value.to_le_bytes());
+        }
+    }
+
+    lumps
+}
+
+fn write(lumps: &[Vec<u8>; HEADER_LUMPS]) -> Vec<u8> {
+    let mut bytes = 30u32.to_le_bytes().to_vec();
+    bytes.resize(4 + HEADER_LUMPS * 8, 0);
+    for (i, lump) in lumps.iter().enumerate() {
+        let offset = bytes.len() as u32;
+        bytes[4 + i * 8..8 + i * 8].copy_from_slice(&offset.to_le_bytes());
+        bytes[8 + i * 8..12 + i * 8].copy_from_slice(&(lump.len() as u32).to_le_bytes());
+        bytes.extend(lump);
+        bytes.resize((bytes.len() + 3) & !3, 0);
+    }
+
+    bytes
+}
+
+fn bsp() -> Vec<u8> {
+    write(&lumps())
+}
+
+#[test]
+fn keeps_entity_order() {
+    let map = decompile(&read_bsp(&bsp()).unwrap());
+
+    let classnames: Vec<_> = map
+        .entities
+        .iter()
+        .map(|entity| entity.entity.classname().unwrap())
+        .collect();
+    assert_eq!(classnames, ["worldspawn", "info_player_start", "func_wall"]);
+
+    let [world, start, wall] = &map.entities[..] else {
+        unreachable!();
+    };
+    assert_eq!(world.entity.get("mapversion"), Some("220"));
+    assert_eq!(world.brushes.len(), 1);
+    assert!(start.brushes.is_empty());
+    assert_eq!(start.entity.get("origin"), Some("0 0 32"));
+    assert_eq!(wall.entity.get("model"), None);
+    assert_eq!(wall.brushes.len(), 1);
+}
+
+#[test]
+fn builds_closed_brushes() {
+    let map = decompile(&read_bsp(&bsp()).unwrap());
+
+    let bounds: Vec<_> = map
+        .entities
+        .iter()
+        .flat_map(|entity| &entity.brushes)
+        .map(|brush| {
+            assert_eq!(brush.faces.len(), 6);
+            assert!(brush.faces.iter().all(|f| f.texture == HIDDEN_TEXTURE));
+            let polyhedron = brush.polyhedron();
+            assert!(polyhedron.is_closed());
+            let bounds = polyhedron.bounds().unwrap();
+            (bounds.min, bounds.max)
+        })
+        .collect();
+    assert_eq!(
+        bounds,
+        [
+            (Vec3::new(-16.0, -16.0, -16.0), Vec3::new(16.0, 16.0, 0.0)),
+            (Vec3::new(48.0, 0.0, 0.0), Vec3::new(64.0, 16.0, 16.0)),
+        ]
+    );
+
+    // The brushes survive being written out
+    let map = parse_map(&write_map(&map).unwrap()).unwrap();
+    assert_eq!(map.format, MapFormat::Valve220);
+    assert!(map
+        .entities
+        .iter()
+        .flat_map(|entity| &entity.brushes)
+        .all(|brush| brush.polyhedron().is_closed()));
+}
+
+#[test]
+fn skips_corrupt_trees() {
+    // A node that is its own front child
+    let mut cycle = lumps();
+    cycle[LUMP_NODES][4..6].copy_from_slice(&0i16.to_le_bytes());
+    let map = decompile(&read_bsp(&write(&cycle)).unwrap());
+    assert_eq!(map.entities[0].brushes.len(), 1);
+
+    // A node with a missing plane
+    let mut missing_plane = lumps();
+    missing_plane[LUMP_NODES][24..28].copy_from_slice(&9u32.to_le_bytes());
+    let map = decompile(&read_bsp(&write(&missing_plane)).unwrap());
+    assert!(map.entities[2].brushes.is_empty());
+}
+
+#[test]
+fn ignores_texture_axes_without_length() {
+    // A triangle on top of the floor, with a texinfo whose s axis is zero
+    let mut lumps = lumps();
+    for vertex in [[0.0f32, 0.0, 0.0], [16.0, 0.0, 0.0], [0.0, 16.0, 0.0]] {
+        vertex
+            .iter()
+            .for_each(|v| lumps[LUMP_VERTICES].extend(v.to_le_bytes()));
+    }
+    for (start, end) in [(0u16, 0u16), (0, 1), (1, 2), (2, 0)] {
+        lumps[LUMP_EDGES].extend(start.to_le_bytes());
+        lumps[LUMP_EDGES].extend(end.to_le_bytes());
+    }
+    for surf_edge in [1i32, 2, 3] {
+        lumps[LUMP_SURFEDGES].extend(surf_edge.to_le_bytes());
+    }
+    for value in [0.0f32, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0] {
+        lumps[LUMP_TEXINFO].extend(value.to_le_bytes());
+    }
+    lumps[LUMP_TEXINFO].extend([0; 8]);
+    // Plane, side, first edge, edge count, texinfo, styles, lightmap
+    let lump = &mut lumps[LUMP_FACES];
+    [0u16, 0].iter().for_each(|v| lump.extend(v.to_le_bytes()));
+    lump.extend(0i32.to_le_bytes());
+    [3u16, 0].iter().for_each(|v| lump.extend(v.to_le_bytes()));
+    lump.extend([255; 4]);
+    lump.extend((-1i32).to_le_bytes());
+    // The floor's node holds the face
+    lumps[LUMP_NODES][22..24].copy_from_slice(&1u16.to_le_bytes());
+
+    let map = decompile(&read_bsp(&write(&lumps)).unwrap());
+    let faces = &map.entities[0].brushes[0].faces;
+    let top = faces
+        .iter()
+        .find(|face| face.plane().0 == Vec3::new(0.0, 0.0, 1.0))
+        .unwrap();
+    assert_eq!(top.texture, HIDDEN_TEXTURE);
+    assert_eq!(top.scale, [1.0, 1.0]);
+
+    // No infinite or NaN values end up in the map
+    assert!(write_map(&map)
+        .unwrap()
+        .split_whitespace()
+        .all(|word| word.parse::<f32>().map_or(true, f32::is_finite)));
+}
@@ -0,0 +1,171 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use bsp_rs::Vec3;
+use map_rs::*;
+
+const QUAKE: &str = r#"// entity 0
+{
+"classname" "worldspawn"
+"wad" "\quake\id1\gfx.wad"
+// brush 0
+{
+( -16 -16 -16 ) ( -16 -15 -16 ) ( -16 -16 -15 ) wall 0 0 0 1 1
+( -16 -16 -16 ) ( -16 -16 -15 ) ( -15 -16 -16 ) wall 4 -8 90 1 1
+( -16 -16 -16 ) ( -15 -16 -16 ) ( -16 -15 -16 ) floor 0 0 0 1 1
+( 16 16 16 ) ( 16 17 16 ) ( 17 16 16 ) floor 16 32 30 0.5 2
+( 16 16 16 ) ( 17 16 16 ) ( 16 16 17 ) wall 0 0 0 1 1
+( 16 16 16 ) ( 16 16 17 ) ( 16 17 16 ) wall 0 0 180 -1 1
+}
+}
+// entity 1
+{
+"classname" "light"
+"origin" "0 0 8"
+}
+"#;
+
+const VALVE_220: &str = r#"{
+"classname" "worldspawn"
+"mapversion" "220"
+{
+( -16 -16 -16 ) ( -16 -15 -16 ) ( -16 -16 -15 ) wall [ 0 1 0 0 ] [ 0 0 -1 0 ] 0 1 1
+( -16 -16 -16 ) ( -16 -16 -15 ) ( -15 -16 -16 ) wall [ 1 0 0 4 ] [ 0 0 -1 -8 ] 0 1 1
+( -16 -16 -16 ) ( -15 -16 -16 ) ( -16 -15 -16 ) floor [ 0.7071068 0.7071068 0 0 ] [ 0.7071068 -0.7071068 0 0 ] 45 1 1
+( 16 16 16 ) ( 16 17 16 ) ( 17 16 16 ) floor [ 1 0 0 16 ] [ 0 -1 0 32 ] 0 0.5 2
+( 16 16 16 ) ( 17 16 16 ) ( 16 16 17 ) wall [ 1 0 0 0 ] [ 0 0 -1 0 ] 0 1 1
+( 16 16 16 ) ( 16 16 17 ) ( 16 17 16 ) wall [ 0 1 0 0 ] [ 0 0 -1 0 ] 0 1 1
+}
+}
+{
+"classname" "func_door"
+"angle" "-1"
+{
+( 0 0 0 ) ( 0 1 0 ) ( 0 0 1 ) door [ 0 1 0 0 ] [ 0 0 -1 0 ] 0 1 1
+( 8 0 0 ) ( 8 0 1 ) ( 8 1 0 ) door [ 0 1 0 0 ] [ 0 0 -1 0 ] 0 1 1
+( 0 0 0 ) ( 0 0 1 ) ( 1 0 0 ) door [ 1 0 0 0 ] [ 0 0 -1 0 ] 0 1 1
+( 0 8 0 ) ( 1 8 0 ) ( 0 8 1 ) door [ 1 0 0 0 ] [ 0 0 -1 0 ] 0 1 1
+( 0 0 0 ) ( 1 0 0 ) ( 0 1 0 ) door [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+( 0 0 8 ) ( 0 1 8 ) ( 1 0 8 ) door [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}
+"#;
+
+fn assert_same_vec3(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+}
+
+fn assert_same_maps(a: &Map, b: &Map) {
+    assert_eq!(a.format, b.format);
+    assert_eq!(a.entities.len(), b.entities.len());
+
+    for (a, b) in a.entities.iter().zip(&b.entities) {
+        assert_eq!(a.entity, b.entity);
+        assert_eq!(a.brushes.len(), b.brushes.len());
+
+        for (a, b) in a.brushes.iter().zip(&b.brushes) {
+            assert_eq!(a.faces.len(), b.faces.len());
+
+            for (a, b) in a.faces.iter().zip(&b.faces) {
+                assert_eq!(a.points, b.points);
+                assert_eq!(a.texture, b.texture);
+                for (a, b) in [(a.u, b.u), (a.v, b.v)] {
+                    assert_same_vec3(a.axis, b.axis);
+                    assert_eq!(a.shift, b.shift);
+                }
+                assert_eq!(a.rotation, b.rotation);
+                assert_eq!(a.scale, b.scale);
+            }
+        }
+    }
+}
+
+#[test]
+fn round_trips_quake_maps() {
+    let map = parse_map(QUAKE).unwrap();
+    assert_eq!(map.format, MapFormat::Quake);
+    assert_eq!(map.entities.len(), 2);
+    assert_eq!(
+        map.entities[0].entity.get("wad"),
+        Some("\\quake\\id1\\gfx.wad")
+    );
+    assert_eq!(map.entities[0].brushes[0].faces.len(), 6);
+    assert!(map.entities[1].brushes.is_empty());
+
+    let face = &map.entities[0].brushes[0].faces[3];
+    assert_eq!(face.texture, "floor");
+    assert_eq!((face.u.shift, face.v.shift), (16.0, 32.0));
+    assert_eq!((face.rotation, face.scale), (30.0, [0.5, 2.0]));
+
+    let data = write_map(&map).unwrap();
+    assert_eq!(data, QUAKE);
+    assert_same_maps(&parse_map(&data).unwrap(), &map);
+}
+
+#[test]
+fn round_trips_valve_220_maps() {
+    let map = parse_map(VALVE_220).unwrap();
+    assert_eq!(map.format, MapFormat::Valve220);
+    assert_eq!(map.entities[1].brushes.len(), 1);
+
+    let face = &map.entities[0].brushes[0].faces[2];
+    assert_same_vec3(face.u.axis, Vec3::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0));
+    assert_same_vec3(face.v.axis, Vec3::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0));
+    assert_eq!(face.rotation, 45.0);
+
+    let data = write_map(&map).unwrap();
+    let reparsed = parse_map(&data).unwrap();
+    assert_same_maps(&reparsed, &map);
+    assert_eq!(write_map(&reparsed).unwrap(), data);
+}
+
+#[test]
+fn rejects_double_quotes() {
+    let mut map = parse_map(QUAKE).unwrap();
+    map.entities[1].entity.set("targetname", "say \"hi\"");
+
+    assert_eq!(
+        write_map(&map).unwrap_err(),
+        bsp_rs::EntityWriteError {
+            entity: 1,
+            key: "targetname".to_string(),
+        }
+    );
+}
+
+#[test]
+fn aligns_rotated_and_scaled_quake_faces() {
+    // Floor facing up, whose base axes are +X and -Y
+    let points = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+    ];
+
+    let face = BrushFace::with_quake_alignment(points, "floor", [0.0, 0.0], 0.0, [1.0, 1.0]);
+    assert_eq!(face.u.axis, Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(face.v.axis, Vec3::new(0.0, -1.0, 0.0));
+
+    let face = BrushFace::with_quake_alignment(points, "floor", [8.0, -4.0], 90.0, [2.0, 0.5]);
+    assert_eq!(face.u.axis, Vec3::new(0.0, 1.0, 0.0));
+    assert_eq!(face.v.axis, Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!((face.u.shift, face.v.shift), (8.0, -4.0));
+    assert_eq!(face.scale, [2.0, 0.5]);
+
+    let face = BrushFace::with_quake_alignment(points, "floor", [0.0, 0.0], 30.0, [0.0, -1.0]);
+    let (sin, cos) = 30f32.to_radians().sin_cos();
+    assert_same_vec3(face.u.axis, Vec3::new(cos, sin, 0.0));
+    assert_same_vec3(face.v.axis, Vec3::new(sin, -cos, 0.0));
+    // A scale of 0 means 1, negative ones flip the texture
+    assert_eq!(face.scale, [1.0, -1.0]);
+
+    // Walls facing X rotate around it, mixing Y and Z
+    let points = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+    let face = BrushFace::with_quake_alignment(points, "wall", [0.0, 0.0], 90.0, [1.0, 1.0]);
+    assert_eq!(face.plane().0, Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(face.u.axis, Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(face.v.axis, Vec3::new(0.0, 1.0, 0.0));
+}